@@ -0,0 +1,3 @@
+-- migrations/20261018120000_add_retries_to_issue_delivery_queue.sql
+ALTER TABLE issue_delivery_queue ADD COLUMN n_attempts INT NOT NULL DEFAULT 0;
+ALTER TABLE issue_delivery_queue ADD COLUMN next_attempt_at timestamptz NOT NULL DEFAULT now();
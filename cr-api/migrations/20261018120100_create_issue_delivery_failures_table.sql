@@ -0,0 +1,10 @@
+-- migrations/20261018120100_create_issue_delivery_failures_table.sql
+CREATE TABLE issue_delivery_failures (
+  newsletter_issue_id uuid NOT NULL
+    REFERENCES newsletter_issues (newsletter_issue_id),
+  subscriber_email TEXT NOT NULL,
+  n_attempts INT NOT NULL,
+  last_error TEXT NOT NULL,
+  failed_at timestamptz NOT NULL,
+  PRIMARY KEY(newsletter_issue_id, subscriber_email)
+);
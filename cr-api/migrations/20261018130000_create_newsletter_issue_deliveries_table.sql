@@ -0,0 +1,10 @@
+-- migrations/20261018130000_create_newsletter_issue_deliveries_table.sql
+CREATE TABLE newsletter_issue_deliveries (
+  newsletter_issue_id uuid NOT NULL
+    REFERENCES newsletter_issues (newsletter_issue_id),
+  subscriber_email TEXT NOT NULL,
+  status TEXT NOT NULL,
+  error TEXT NULL,
+  recorded_at timestamptz NOT NULL,
+  PRIMARY KEY(newsletter_issue_id, subscriber_email)
+);
@@ -0,0 +1,4 @@
+-- migrations/20261018140000_add_scheduling_to_newsletter_issues.sql
+ALTER TABLE newsletter_issues ADD COLUMN status TEXT NOT NULL DEFAULT 'published';
+ALTER TABLE newsletter_issues ADD COLUMN scheduled_for timestamptz NULL;
+ALTER TABLE newsletter_issues ALTER COLUMN published_at DROP NOT NULL;
@@ -0,0 +1,3 @@
+-- migrations/20261018150000_add_markdown_content_to_newsletter_issues.sql
+-- Keep the Markdown source of issues authored in Markdown, so they can be re-rendered later
+ALTER TABLE newsletter_issues ADD COLUMN markdown_content TEXT NULL;
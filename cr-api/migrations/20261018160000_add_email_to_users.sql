@@ -0,0 +1,2 @@
+-- migrations/20261018160000_add_email_to_users.sql
+ALTER TABLE users ADD COLUMN email TEXT NULL;
@@ -0,0 +1,14 @@
+-- migrations/20261018170000_create_subscriber_events_table.sql
+CREATE TABLE subscriber_events (
+  id uuid NOT NULL PRIMARY KEY,
+  subscriber_id uuid NULL
+    REFERENCES subscriptions (id) ON DELETE SET NULL,
+  email TEXT NOT NULL,
+  event_type TEXT NOT NULL,
+  detail TEXT NOT NULL,
+  provider_event_id BIGINT NOT NULL,
+  payload TEXT NOT NULL,
+  received_at timestamptz NOT NULL,
+  UNIQUE (event_type, provider_event_id)
+);
+CREATE INDEX subscriber_events_email_idx ON subscriber_events (email);
@@ -0,0 +1,14 @@
+-- migrations/20261018180000_create_suppressions_table.sql
+-- addresses are stored lowercased, so that lookups don't depend on how an address was typed
+CREATE TABLE suppressions (
+  email TEXT NOT NULL PRIMARY KEY,
+  reason TEXT NOT NULL,
+  source TEXT NOT NULL,
+  created_at timestamptz NOT NULL
+);
+-- addresses which already bounced or complained are suppressed from the start
+INSERT INTO suppressions (email, reason, source, created_at)
+SELECT lower(email), status, 'postmark', now()
+FROM subscriptions
+WHERE status IN ('bounced', 'complained')
+ON CONFLICT DO NOTHING;
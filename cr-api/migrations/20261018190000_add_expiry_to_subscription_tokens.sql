@@ -0,0 +1,4 @@
+-- migrations/20261018190000_add_expiry_to_subscription_tokens.sql
+-- tokens issued before this migration start their validity window now
+ALTER TABLE subscription_tokens ADD COLUMN created_at timestamptz NOT NULL DEFAULT now();
+ALTER TABLE subscription_tokens ADD COLUMN consumed_at timestamptz NULL;
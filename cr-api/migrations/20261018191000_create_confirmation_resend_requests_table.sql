@@ -0,0 +1,9 @@
+-- migrations/20261018191000_create_confirmation_resend_requests_table.sql
+-- every accepted request to resend a confirmation email, used to throttle them per address and per client
+CREATE TABLE confirmation_resend_requests(
+    email TEXT NOT NULL,
+    client_ip TEXT NOT NULL,
+    requested_at timestamptz NOT NULL
+);
+CREATE INDEX confirmation_resend_requests_email_idx ON confirmation_resend_requests (email, requested_at);
+CREATE INDEX confirmation_resend_requests_client_ip_idx ON confirmation_resend_requests (client_ip, requested_at);
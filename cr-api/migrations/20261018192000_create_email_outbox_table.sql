@@ -0,0 +1,16 @@
+-- migrations/20261018192000_create_email_outbox_table.sql
+-- transactional emails written alongside the change that triggers them, sent by the outbox worker
+CREATE TABLE email_outbox(
+    id uuid NOT NULL,
+    recipient TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    html_content TEXT NOT NULL,
+    text_content TEXT NOT NULL,
+    n_attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at timestamptz NOT NULL DEFAULT now(),
+    last_error TEXT NULL,
+    failed_at timestamptz NULL,
+    created_at timestamptz NOT NULL,
+    PRIMARY KEY (id)
+);
+CREATE INDEX email_outbox_next_attempt_at_idx ON email_outbox (next_attempt_at) WHERE failed_at IS NULL;
@@ -0,0 +1,21 @@
+-- migrations/20261018193000_add_slug_to_newsletter_issues.sql
+-- Give published issues the slug of their public archive page, issues published before now get one
+-- built the same way as IssueSlug::new: the ASCII words of the title, lowercased and each followed by a dash,
+-- for as long as they fit within 60 characters, then the start of the id
+ALTER TABLE newsletter_issues ADD COLUMN slug TEXT NULL;
+UPDATE newsletter_issues
+SET slug = concat(
+  (
+    SELECT string_agg(lower(word) || '-', '' ORDER BY n)
+    FROM (
+      SELECT word, n, sum(length(word) + 1) OVER (ORDER BY n) AS running_length
+      FROM regexp_split_to_table(title, '[^a-zA-Z0-9]+') WITH ORDINALITY AS words(word, n)
+      WHERE word <> ''
+    ) title_words
+    WHERE running_length <= 60
+  ),
+  left(replace(newsletter_issue_id::text, '-', ''), 8)
+)
+WHERE status = 'published';
+CREATE UNIQUE INDEX newsletter_issues_slug_idx ON newsletter_issues (slug);
+CREATE INDEX newsletter_issues_published_at_idx ON newsletter_issues (published_at) WHERE status = 'published';
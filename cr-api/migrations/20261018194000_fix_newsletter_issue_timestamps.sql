@@ -0,0 +1,15 @@
+-- migrations/20261018194000_fix_newsletter_issue_timestamps.sql
+-- published_at has always been filled in with now(), store it as the timestamp it is so that issues sort
+-- and filter by date reliably; keep track of when each issue was created and last changed as well
+ALTER TABLE newsletter_issues
+  ALTER COLUMN published_at TYPE timestamptz USING published_at::timestamptz;
+ALTER TABLE newsletter_issues ADD COLUMN created_at timestamptz NULL;
+ALTER TABLE newsletter_issues ADD COLUMN updated_at timestamptz NULL;
+UPDATE newsletter_issues
+SET
+  created_at = coalesce(published_at, now()),
+  updated_at = coalesce(published_at, now());
+ALTER TABLE newsletter_issues ALTER COLUMN created_at SET NOT NULL;
+ALTER TABLE newsletter_issues ALTER COLUMN created_at SET DEFAULT now();
+ALTER TABLE newsletter_issues ALTER COLUMN updated_at SET NOT NULL;
+ALTER TABLE newsletter_issues ALTER COLUMN updated_at SET DEFAULT now();
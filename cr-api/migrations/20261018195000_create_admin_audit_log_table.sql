@@ -0,0 +1,13 @@
+-- migrations/20261018195000_create_admin_audit_log_table.sql
+-- Record every change an admin makes to a subscriber by hand; the subscriber id is kept without a foreign
+-- key, so that the entry outlives a deleted subscriber
+CREATE TABLE admin_audit_log (
+  id uuid NOT NULL PRIMARY KEY,
+  user_id uuid NOT NULL
+    REFERENCES users (user_id),
+  action TEXT NOT NULL,
+  subscriber_id uuid NOT NULL,
+  subscriber_email TEXT NOT NULL,
+  recorded_at timestamptz NOT NULL
+);
+CREATE INDEX admin_audit_log_recorded_at_idx ON admin_audit_log (recorded_at);
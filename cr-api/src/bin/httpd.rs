@@ -3,12 +3,17 @@
 // dependencies
 use anyhow::{Context, Result};
 use cr_api::configuration::get_configuration;
+use cr_api::email_outbox_worker::run_outbox_until_stopped;
 use cr_api::idempotency_cleanup_worker::run_cleanup_until_stopped;
-use cr_api::issue_delivery_worker::run_delivery_until_stopped;
+use cr_api::issue_delivery_worker::DeliveryWorkers;
+use cr_api::newsletter_scheduling_worker::run_scheduling_until_stopped;
+use cr_api::shutdown::{wait_for_signal, Shutdown};
 use cr_api::startup::Application;
 use cr_api::telemetry::{get_subscriber, init_subscriber};
 use std::fmt::{Debug, Display};
-use tokio::task::JoinError;
+use std::future::Future;
+use tokio::sync::watch;
+use tokio::task::{JoinError, JoinSet};
 
 // report exit function
 fn report_exit(task_name: &str, outcome: Result<Result<(), impl Debug + Display>, JoinError>) {
@@ -35,6 +40,25 @@ fn report_exit(task_name: &str, outcome: Result<Result<(), impl Debug + Display>
     }
 }
 
+// spawns a task which reports how it exited and then requests shutdown of all the others
+fn spawn_task<F, E>(
+    tasks: &mut JoinSet<()>,
+    shutdown_sender: &watch::Sender<bool>,
+    task_name: String,
+    task: F,
+) where
+    F: Future<Output = Result<(), E>> + Send + 'static,
+    E: Debug + Display + Send + 'static,
+{
+    let shutdown_sender = shutdown_sender.clone();
+    tasks.spawn(async move {
+        // spawned on its own so that a panic is reported as well
+        let outcome = tokio::spawn(task).await;
+        report_exit(&task_name, outcome);
+        let _ = shutdown_sender.send(true);
+    });
+}
+
 // main function
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -46,22 +70,81 @@ async fn main() -> Result<()> {
     let configuration =
         get_configuration().context("Failed to get the application configuration settings...")?;
 
+    // build the email client once, every sender shares its rate limit
+    let email_client = configuration.email_client.clone().client();
+
     // return an instance of the application
-    let application = Application::build(configuration.clone())
+    let application = Application::build(configuration.clone(), email_client.clone())
         .await
         .context("Failed to build the application...")?;
-    let application_task = tokio::spawn(application.run_until_stopped());
 
-    // define the delivery processing service worker
-    let email_delivery_task = tokio::spawn(run_delivery_until_stopped(configuration.clone()));
+    // every task stops once shutdown is requested, be it by a signal or by another task exiting
+    let (shutdown_sender, shutdown) = Shutdown::channel();
+    let mut tasks = JoinSet::new();
+    spawn_task(
+        &mut tasks,
+        &shutdown_sender,
+        "API".into(),
+        application.run_until_shutdown(shutdown.clone()),
+    );
+
+    // define the delivery processing service workers
+    let delivery_workers = DeliveryWorkers::build(configuration.clone(), email_client.clone())
+        .context("Failed to build the email delivery workers...")?;
+    for worker_id in 0..delivery_workers.n_workers() {
+        spawn_task(
+            &mut tasks,
+            &shutdown_sender,
+            format!("Email delivery worker {}", worker_id),
+            delivery_workers
+                .clone()
+                .run_until_stopped(worker_id, shutdown.clone()),
+        );
+    }
+
+    // define the email outbox service worker
+    spawn_task(
+        &mut tasks,
+        &shutdown_sender,
+        "Email outbox worker".into(),
+        run_outbox_until_stopped(configuration.clone(), email_client, shutdown.clone()),
+    );
+
+    // define the newsletter scheduling service worker
+    spawn_task(
+        &mut tasks,
+        &shutdown_sender,
+        "Newsletter scheduling worker".into(),
+        run_scheduling_until_stopped(configuration.clone(), shutdown.clone()),
+    );
 
     // define the idempotency cleanup service worker
-    let idempotency_cleanup_task = tokio::spawn(run_cleanup_until_stopped(configuration));
+    let drain_timeout = configuration.application.drain_timeout();
+    spawn_task(
+        &mut tasks,
+        &shutdown_sender,
+        "Idempotency cleanup worker".into(),
+        run_cleanup_until_stopped(configuration, shutdown.clone()),
+    );
 
+    let mut task_exited = shutdown.clone();
     tokio::select! {
-        o = application_task => report_exit("API", o),
-        o = email_delivery_task => report_exit("Email delivery worker", o),
-        o = idempotency_cleanup_task => report_exit("Idempotency cleanup worker", o),
+        _ = wait_for_signal() => tracing::info!("Received a shutdown signal"),
+        _ = task_exited.requested() => {}
     };
+
+    // give the remaining tasks the drain timeout to finish what they are doing
+    let _ = shutdown_sender.send(true);
+    let drained = tokio::time::timeout(drain_timeout, async {
+        while tasks.join_next().await.is_some() {}
+    })
+    .await;
+    if drained.is_err() {
+        tracing::warn!(
+            "{} task(s) didn't stop within the drain timeout and were aborted",
+            tasks.len()
+        );
+        tasks.shutdown().await;
+    }
     Ok(())
 }
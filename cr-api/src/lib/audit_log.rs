@@ -0,0 +1,82 @@
+// src/lib/audit_log.rs
+
+// the audit log, a record of the changes admins make to subscribers by hand
+
+// dependencies
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+// an enum to represent the changes to a subscriber which are recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminAction {
+    ConfirmSubscriber,
+    UnsubscribeSubscriber,
+    DeleteSubscriber,
+    ImportSubscriber,
+}
+
+// implementation to return the string stored in the audit log for each action
+impl AdminAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminAction::ConfirmSubscriber => "confirm_subscriber",
+            AdminAction::UnsubscribeSubscriber => "unsubscribe_subscriber",
+            AdminAction::DeleteSubscriber => "delete_subscriber",
+            AdminAction::ImportSubscriber => "import_subscriber",
+        }
+    }
+}
+
+// a struct to represent an entry of the audit log, along with the name of the admin who made the change
+pub struct AuditLogEntry {
+    pub username: String,
+    pub action: String,
+    pub subscriber_email: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+// function to record a change made by an admin, as part of the transaction making it
+#[tracing::instrument(skip(transaction, subscriber_email))]
+pub async fn record_admin_action(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    action: AdminAction,
+    subscriber_id: Uuid,
+    subscriber_email: &str,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO admin_audit_log (id, user_id, action, subscriber_id, subscriber_email, recorded_at)
+        VALUES ($1, $2, $3, $4, $5, now())
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        action.as_str(),
+        subscriber_id,
+        subscriber_email,
+    );
+    transaction.execute(query).await?;
+    Ok(())
+}
+
+// function to retrieve the most recent entries of the audit log, newest first
+#[tracing::instrument(skip(pool))]
+pub async fn get_recent_admin_actions(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<AuditLogEntry>, sqlx::Error> {
+    sqlx::query_as!(
+        AuditLogEntry,
+        r#"
+        SELECT users.username, action, subscriber_email, recorded_at
+        FROM admin_audit_log
+        JOIN users ON users.user_id = admin_audit_log.user_id
+        ORDER BY recorded_at DESC, id
+        LIMIT $1
+        "#,
+        limit,
+    )
+    .fetch_all(pool)
+    .await
+}
@@ -2,7 +2,8 @@
 
 // dependencies
 use crate::domain::SubscriberEmail;
-use crate::email_client::EmailClient;
+use crate::email_client::{EmailClient, FileTransport, SmtpTransport};
+use crate::rate_limiter::RateLimiter;
 use confik::{Configuration, EnvSource, Error, FileSource};
 use serde::Deserialize;
 use serde_aux::field_attributes::deserialize_number_from_string;
@@ -10,6 +11,7 @@ use sqlx::{
     postgres::{PgConnectOptions, PgSslMode},
     ConnectOptions,
 };
+use std::num::NonZeroU32;
 
 // a struct to hold a type for settings
 #[derive(Clone, Deserialize, Configuration)]
@@ -17,6 +19,9 @@ pub struct Settings {
     pub database: DatabaseSettings,
     pub application: ApplicationSettings,
     pub email_client: EmailClientSettings,
+    pub issue_delivery: IssueDeliverySettings,
+    pub email_outbox: EmailOutboxSettings,
+    pub postmark_webhook: PostmarkWebhookSettings,
     pub redis: RedisSettings,
 }
 
@@ -27,6 +32,14 @@ pub struct RedisSettings {
     pub uri: String,
 }
 
+// a struct to hold a type for the basic auth credentials Postmark uses to call our webhook
+#[derive(Clone, Deserialize, Configuration)]
+pub struct PostmarkWebhookSettings {
+    pub username: String,
+    #[confik(secret)]
+    pub password: String,
+}
+
 // a struct to hold a type for application settings
 #[derive(Clone, Deserialize, Configuration)]
 pub struct ApplicationSettings {
@@ -36,28 +49,123 @@ pub struct ApplicationSettings {
     pub base_url: String,
     #[confik(secret)]
     pub hmac_secret: String,
+    pub drain_timeout_seconds: u64,
+    pub trusted_proxy_hops: u32,
+}
+
+// an enum to hold the ways emails can be sent, be it through Postmark, an SMTP relay or into a directory
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailTransportKind {
+    Postmark,
+    Smtp,
+    File,
+}
+
+impl Configuration for EmailTransportKind {
+    type Builder = Option<Self>;
+}
+
+// implement the drain timeout function for the application
+impl ApplicationSettings {
+    // how long the server and the background workers get to wrap up once shutdown is requested
+    pub fn drain_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.drain_timeout_seconds)
+    }
 }
 
 // a struct to hold a type for email client settings
 #[derive(Clone, Deserialize, Configuration)]
 pub struct EmailClientSettings {
+    pub transport: EmailTransportKind,
     pub base_url: String,
     pub sender_email: String,
     #[confik(secret)]
     pub authorization_token: String,
     pub timeout_milliseconds: u64,
+    pub max_messages_per_second: Option<MessageLimit>,
+    pub max_messages_per_hour: Option<MessageLimit>,
+    pub smtp: Option<SmtpSettings>,
+    pub file: Option<FileSinkSettings>,
+}
+
+// a struct to represent a limit on the messages sent within a period; a limit of zero would never let a message
+// through, so it is rejected when the configuration is loaded, leave the limit out to not limit anything
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(transparent)]
+pub struct MessageLimit(pub NonZeroU32);
+
+impl Configuration for MessageLimit {
+    type Builder = Option<Self>;
+}
+
+// a struct to hold a type for the SMTP transport settings
+#[derive(Clone, Deserialize, Configuration)]
+pub struct SmtpSettings {
+    pub host: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub port: u16,
+    pub username: Option<String>,
+    #[confik(secret)]
+    pub password: Option<String>,
+    pub require_tls: bool,
+}
+
+// a struct to hold a type for the file transport settings
+#[derive(Clone, Deserialize, Configuration)]
+pub struct FileSinkSettings {
+    pub directory: String,
 }
 
 // implement sender and timeout functions for Email Client
 impl EmailClientSettings {
+    // builds the email client along with its rate limiter, meant to be called once and the client cloned for
+    // every sender, as each call starts a rate limit of its own
     pub fn client(self) -> EmailClient {
         let sender_email = self.sender().expect("Invalid sender email address.");
         let timeout = self.timeout();
-        EmailClient::new(
-            self.base_url,
-            sender_email,
-            self.authorization_token.into(),
-            timeout,
+        let rate_limiter = self.rate_limiter();
+        let client = match self.transport {
+            EmailTransportKind::Postmark => EmailClient::new(
+                self.base_url,
+                sender_email,
+                self.authorization_token.into(),
+                timeout,
+            ),
+            EmailTransportKind::Smtp => {
+                let smtp = self
+                    .smtp
+                    .expect("Missing SMTP settings for the email client.");
+                let credentials = smtp.username.zip(smtp.password.map(Into::into));
+                let transport = SmtpTransport::new(
+                    &smtp.host,
+                    smtp.port,
+                    credentials,
+                    smtp.require_tls,
+                    timeout,
+                )
+                .expect("Failed to set up the SMTP transport.");
+                EmailClient::with_transport(sender_email, transport)
+            }
+            EmailTransportKind::File => {
+                let file = self
+                    .file
+                    .expect("Missing file settings for the email client.");
+                let transport = FileTransport::new(file.directory.into())
+                    .expect("Failed to set up the file transport.");
+                EmailClient::with_transport(sender_email, transport)
+            }
+        };
+        match rate_limiter {
+            Some(rate_limiter) => client.with_rate_limiter(rate_limiter),
+            None => client,
+        }
+    }
+
+    pub fn rate_limiter(&self) -> Option<RateLimiter> {
+        RateLimiter::new(
+            self.max_messages_per_second.map(|limit| limit.0),
+            self.max_messages_per_hour.map(|limit| limit.0),
         )
     }
 
@@ -70,6 +178,47 @@ impl EmailClientSettings {
     }
 }
 
+// a struct to hold a type for the issue delivery worker settings
+#[derive(Clone, Debug, Deserialize, Configuration)]
+pub struct IssueDeliverySettings {
+    pub n_workers: u32,
+    pub batch_size: u32,
+    pub max_retries: u32,
+    pub retry_base_delay_milliseconds: u64,
+    pub retry_max_delay_milliseconds: u64,
+}
+
+// implement the retry delay functions for the issue delivery worker
+impl IssueDeliverySettings {
+    pub fn retry_base_delay(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.retry_base_delay_milliseconds)
+    }
+
+    pub fn retry_max_delay(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.retry_max_delay_milliseconds)
+    }
+}
+
+// a struct to hold a type for the email outbox worker settings
+#[derive(Clone, Debug, Deserialize, Configuration)]
+pub struct EmailOutboxSettings {
+    pub batch_size: u32,
+    pub max_retries: u32,
+    pub retry_base_delay_milliseconds: u64,
+    pub retry_max_delay_milliseconds: u64,
+}
+
+// implement the retry delay functions for the email outbox worker
+impl EmailOutboxSettings {
+    pub fn retry_base_delay(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.retry_base_delay_milliseconds)
+    }
+
+    pub fn retry_max_delay(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.retry_max_delay_milliseconds)
+    }
+}
+
 // a struct to hold a type for database settings
 #[derive(Clone, Deserialize, Configuration)]
 pub struct DatabaseSettings {
@@ -165,3 +314,40 @@ pub fn get_configuration() -> Result<Settings, Error> {
         .try_build()?;
     Ok(settings)
 }
+
+// unit tests for loading the configuration
+#[cfg(test)]
+mod tests {
+    use super::EmailClientSettings;
+    use confik::{Configuration, TomlSource};
+
+    fn email_client_settings(limits: &str) -> Result<EmailClientSettings, confik::Error> {
+        let toml = format!(
+            r#"
+            transport = "postmark"
+            base_url = "http://127.0.0.1"
+            sender_email = "test@gmail.com"
+            authorization_token = "my-secret-token"
+            timeout_milliseconds = 10000
+            {}
+            "#,
+            limits
+        );
+        let settings = EmailClientSettings::builder()
+            .override_with(TomlSource::new(&toml).allow_secrets())
+            .try_build();
+        settings
+    }
+
+    #[test]
+    fn message_limits_are_optional() {
+        let settings = email_client_settings("max_messages_per_hour = 100").unwrap();
+        assert!(settings.max_messages_per_second.is_none());
+        assert_eq!(settings.max_messages_per_hour.unwrap().0.get(), 100);
+    }
+
+    #[test]
+    fn a_message_limit_of_zero_is_rejected() {
+        assert!(email_client_settings("max_messages_per_second = 0").is_err());
+    }
+}
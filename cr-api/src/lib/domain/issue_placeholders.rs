@@ -0,0 +1,272 @@
+// src/lib/domain/issue_placeholders.rs
+
+// domain issue placeholders type
+
+// the placeholders a newsletter issue may use, written as `{{ name }}`
+pub const PLACEHOLDERS: [&str; 3] = ["name", "email", "unsubscribe_url"];
+
+// a piece of issue content, either literal text or a placeholder
+enum Segment<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+// the html elements whose content is shown as it is written, placeholders are never expanded within them
+const CODE_ELEMENTS: [&str; 2] = ["code", "pre"];
+
+// returns the length of the code element starting the content, up to and including its closing tag, or the whole
+// content if it is never closed
+fn code_element_len(content: &str) -> Option<usize> {
+    let tag = content.strip_prefix('<')?;
+    CODE_ELEMENTS.iter().find_map(|element| {
+        let follows = tag.get(element.len()..)?.chars().next()?;
+        if !tag[..element.len()].eq_ignore_ascii_case(element)
+            || !(follows == '>' || follows.is_ascii_whitespace())
+        {
+            return None;
+        }
+        let closing = format!("</{}>", element);
+        let len = content
+            .to_ascii_lowercase()
+            .find(&closing)
+            .map_or(content.len(), |close| close + closing.len());
+        Some(len)
+    })
+}
+
+// splits content into text and placeholders; braces around anything but a single word, as in
+// `{{}}` in a code sample, are kept as text, and `\{{` is written out as literal braces
+// in html content, nothing within a `<code>` or `<pre>` element is a placeholder, so that code samples such as
+// `println!("{{name}}")` are left alone
+fn segments(content: &str, is_html: bool) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut text_start = 0;
+    let mut offset = 0;
+    while let Some(c) = content[offset..].chars().next() {
+        let rest = &content[offset..];
+        if is_html && c == '<' {
+            if let Some(len) = code_element_len(rest) {
+                offset += len;
+                continue;
+            }
+        }
+        if rest.starts_with("\\{{") {
+            segments.push(Segment::Text(&content[text_start..offset]));
+            text_start = offset + 1;
+            offset += 3;
+            continue;
+        }
+        if let Some(after_open) = rest.strip_prefix("{{") {
+            let placeholder = after_open.find("}}").and_then(|close| {
+                let name = after_open[..close].trim();
+                let is_word = name
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                is_word.then_some((name, close))
+            });
+            match placeholder {
+                Some((name, close)) => {
+                    segments.push(Segment::Text(&content[text_start..offset]));
+                    segments.push(Segment::Placeholder(name));
+                    offset += 2 + close + 2;
+                    text_start = offset;
+                }
+                None => offset += 2,
+            }
+            continue;
+        }
+        offset += c.len_utf8();
+    }
+    segments.push(Segment::Text(&content[text_start..]));
+    segments
+}
+
+// checks that the html and plain text content of an issue only use known placeholders, the error names the
+// unknown ones
+pub fn validate_placeholders(html_content: &str, text_content: &str) -> Result<(), String> {
+    let mut unknown: Vec<&str> = Vec::new();
+    for segment in segments(html_content, true)
+        .into_iter()
+        .chain(segments(text_content, false))
+    {
+        if let Segment::Placeholder(name) = segment {
+            if !PLACEHOLDERS.contains(&name) && !unknown.contains(&name) {
+                unknown.push(name);
+            }
+        }
+    }
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    let format = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| format!("{{{{ {} }}}}", name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    Err(format!(
+        "Unknown placeholder(s): {}. The available placeholders are {}.",
+        format(&unknown),
+        format(&PLACEHOLDERS)
+    ))
+}
+
+// a struct to represent the values the placeholders of an issue expand to for a single recipient
+pub struct Personalization<'a> {
+    pub name: &'a str,
+    pub email: &'a str,
+    pub unsubscribe_url: &'a str,
+}
+
+// impl block for the personalization type; expands the placeholders of html and plain text content
+impl Personalization<'_> {
+    fn value(&self, placeholder: &str) -> Option<&str> {
+        match placeholder {
+            "name" => Some(self.name),
+            "email" => Some(self.email),
+            "unsubscribe_url" => Some(self.unsubscribe_url),
+            _ => None,
+        }
+    }
+
+    // unknown placeholders are left as they are, publishing rejects them in the first place
+    fn expand(&self, content: &str, is_html: bool, escape: fn(&str) -> String) -> String {
+        let mut expanded = String::with_capacity(content.len());
+        for segment in segments(content, is_html) {
+            match segment {
+                Segment::Text(text) => expanded.push_str(text),
+                Segment::Placeholder(name) => match self.value(name) {
+                    Some(value) => expanded.push_str(&escape(value)),
+                    None => expanded.push_str(&format!("{{{{ {} }}}}", name)),
+                },
+            }
+        }
+        expanded
+    }
+
+    // expands the placeholders of html content, values are escaped
+    pub fn expand_html(&self, content: &str) -> String {
+        self.expand(content, true, escape_html)
+    }
+
+    // expands the placeholders of plain text content
+    pub fn expand_text(&self, content: &str) -> String {
+        self.expand(content, false, str::to_string)
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// unit tests for the issue placeholders
+#[cfg(test)]
+mod tests {
+    use super::{validate_placeholders, Personalization};
+    use claims::{assert_err, assert_ok};
+
+    fn personalization() -> Personalization<'static> {
+        Personalization {
+            name: "Ursula & co",
+            email: "ursula_le_guin@gmail.com",
+            unsubscribe_url: "https://example.com/unsubscribe?token=a&b",
+        }
+    }
+
+    #[test]
+    fn known_placeholders_are_accepted() {
+        assert_ok!(validate_placeholders(
+            "<p>Hi {{ name }}</p>",
+            "Hi {{ name }} ({{email}}), leave at {{  unsubscribe_url }}."
+        ));
+    }
+
+    #[test]
+    fn unknown_placeholders_are_rejected() {
+        let e = validate_placeholders(
+            "<p>Hi {{ first_name }}</p>",
+            "Hi {{ first_name }}, {{ name }} {{ surname }}",
+        )
+        .unwrap_err();
+        assert!(
+            e.starts_with("Unknown placeholder(s): {{ first_name }}, {{ surname }}. The available")
+        );
+    }
+
+    #[test]
+    fn braces_around_anything_but_a_word_are_plain_text() {
+        for content in [
+            "format!(\"{{}}\")",
+            "{{ a b }}",
+            "{{ 1 + 1 }}",
+            "{{ unclosed",
+        ] {
+            assert_ok!(validate_placeholders(content, content));
+        }
+        assert_err!(validate_placeholders("", "{{}} {{ nope }}"));
+    }
+
+    #[test]
+    fn html_code_samples_are_left_alone() {
+        let html = "<p>Hi {{ name }}</p><pre><code>println!(\"{{name}} {{x}}\");</code></pre>\
+            <CODE class=\"rust\">{{ x }}</CODE><codex>{{ name }}</codex>";
+        assert_ok!(validate_placeholders(html, ""));
+        assert_eq!(
+            personalization().expand_html(html),
+            "<p>Hi Ursula &amp; co</p><pre><code>println!(\"{{name}} {{x}}\");</code></pre>\
+            <CODE class=\"rust\">{{ x }}</CODE><codex>Ursula &amp; co</codex>"
+        );
+        assert_err!(validate_placeholders("<p>{{ x }}</p><pre>", ""));
+        assert_ok!(validate_placeholders("<pre>{{ x }}", ""));
+    }
+
+    #[test]
+    fn escaped_braces_are_written_out_as_literal_braces() {
+        assert_ok!(validate_placeholders(
+            "<p>\\{{ x }}</p>",
+            "println!(\"\\{{name}}\");"
+        ));
+        assert_eq!(
+            personalization().expand_text("println!(\"\\{{name}}\"); {{ name }} \\{{}}"),
+            "println!(\"{{name}}\"); Ursula & co {{}}"
+        );
+        assert_eq!(
+            personalization().expand_html("<p>\\{{ name }}</p>"),
+            "<p>{{ name }}</p>"
+        );
+    }
+
+    #[test]
+    fn html_placeholders_are_expanded_with_escaped_values() {
+        let html = personalization()
+            .expand_html("<p>Hi {{ name }}</p><a href=\"{{ unsubscribe_url }}\">x</a>{{}}");
+        assert_eq!(
+            html,
+            "<p>Hi Ursula &amp; co</p>\
+            <a href=\"https://example.com/unsubscribe?token=a&amp;b\">x</a>{{}}"
+        );
+    }
+
+    #[test]
+    fn text_placeholders_are_expanded_as_they_are() {
+        let text = personalization().expand_text("Hi {{name}}, this went to {{ email }}.");
+        assert_eq!(
+            text,
+            "Hi Ursula & co, this went to ursula_le_guin@gmail.com."
+        );
+    }
+}
@@ -0,0 +1,70 @@
+// src/lib/domain/issue_slug.rs
+
+// domain issue slug type
+
+// dependencies
+use uuid::Uuid;
+
+// the longest part of a slug taken from the issue title, the migration which backfilled slugs uses the same rule
+const MAX_TITLE_LENGTH: usize = 60;
+
+// a struct to represent the slug of a published issue, used in its public address; the title is
+// followed by the start of the issue id, so that issues sharing a title still get their own page
+#[derive(Debug, Clone)]
+pub struct IssueSlug(String);
+
+// impl block for the issue slug type
+impl IssueSlug {
+    pub fn new(title: &str, newsletter_issue_id: Uuid) -> Self {
+        let mut slug = String::new();
+        for word in title
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            if slug.len() + word.len() >= MAX_TITLE_LENGTH {
+                break;
+            }
+            slug.push_str(&word.to_ascii_lowercase());
+            slug.push('-');
+        }
+        slug.push_str(&newsletter_issue_id.simple().to_string()[..8]);
+        Self(slug)
+    }
+}
+
+// impl block to return the inner value of the issue slug type
+impl AsRef<str> for IssueSlug {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// unit tests for the issue slug type
+#[cfg(test)]
+mod tests {
+    use super::IssueSlug;
+    use uuid::Uuid;
+
+    fn issue_id() -> Uuid {
+        Uuid::parse_str("1a2b3c4d-0000-4000-8000-000000000000").unwrap()
+    }
+
+    #[test]
+    fn the_title_is_lowercased_and_joined_with_dashes() {
+        let slug = IssueSlug::new("  Rust 1.80 is out: LazyCell & more! ", issue_id());
+        assert_eq!(slug.as_ref(), "rust-1-80-is-out-lazycell-more-1a2b3c4d");
+    }
+
+    #[test]
+    fn a_title_without_any_ascii_words_only_keeps_the_id() {
+        let slug = IssueSlug::new("¡¿ — ?!", issue_id());
+        assert_eq!(slug.as_ref(), "1a2b3c4d");
+    }
+
+    #[test]
+    fn long_titles_are_cut_at_a_word_boundary() {
+        let slug = IssueSlug::new(&"newsletter ".repeat(20), issue_id());
+        assert!(slug.as_ref().len() <= 60 + 9);
+        assert!(slug.as_ref().ends_with("newsletter-1a2b3c4d"));
+    }
+}
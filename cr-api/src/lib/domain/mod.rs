@@ -2,12 +2,20 @@
 
 // domain module definitions
 
+mod issue_placeholders;
+mod issue_slug;
 mod new_subscriber;
+mod newsletter_markdown;
 mod subscriber_email;
 mod subscriber_name;
 mod templates;
+mod unsubscribe_token;
 
+pub use issue_placeholders::{validate_placeholders, Personalization};
+pub use issue_slug::IssueSlug;
 pub use new_subscriber::NewSubscriber;
+pub use newsletter_markdown::NewsletterMarkdown;
 pub use subscriber_email::SubscriberEmail;
 pub use subscriber_name::SubscriberName;
 pub use templates::*;
+pub use unsubscribe_token::UnsubscribeToken;
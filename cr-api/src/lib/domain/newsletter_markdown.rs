@@ -0,0 +1,152 @@
+// src/lib/domain/newsletter_markdown.rs
+
+// domain newsletter markdown type
+
+// dependencies
+use pulldown_cmark::{html, Event, Options, Parser, Tag, TagEnd};
+
+// a struct to represent the Markdown source of a newsletter issue
+#[derive(Debug, Clone)]
+pub struct NewsletterMarkdown(String);
+
+// impl block for the newsletter markdown type; renders the source into its html and plain text variants
+impl NewsletterMarkdown {
+    // returns an instance of NewsletterMarkdown if the source isn't blank
+    pub fn parse(s: String) -> Result<NewsletterMarkdown, String> {
+        if s.trim().is_empty() {
+            Err("The Markdown content of a newsletter issue can't be empty.".to_string())
+        } else {
+            Ok(Self(s))
+        }
+    }
+
+    fn parser(&self) -> Parser<'_> {
+        Parser::new_ext(
+            &self.0,
+            Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES,
+        )
+    }
+
+    // renders the html body, sanitized so that raw html in the source can't inject scripts and the like
+    pub fn to_html(&self) -> String {
+        let mut unsafe_html = String::new();
+        html::push_html(&mut unsafe_html, self.parser());
+        ammonia::clean(&unsafe_html)
+    }
+
+    // renders a readable plain text alternative, links keep their target next to the link text
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let mut link_targets = Vec::new();
+        let mut in_code_block = false;
+        for event in self.parser() {
+            match event {
+                Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+                Event::Start(Tag::Item) => text.push_str("- "),
+                Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. }) => {
+                    link_targets.push(dest_url)
+                }
+                Event::End(TagEnd::Link | TagEnd::Image) => {
+                    if let Some(dest_url) = link_targets.pop() {
+                        text.push_str(&format!(" ({})", dest_url));
+                    }
+                }
+                Event::End(TagEnd::CodeBlock) => {
+                    in_code_block = false;
+                    text.push_str("\n\n")
+                }
+                Event::End(
+                    TagEnd::Paragraph
+                    | TagEnd::Heading(_)
+                    | TagEnd::BlockQuote(_)
+                    | TagEnd::List(_)
+                    | TagEnd::Table,
+                ) => text.push_str("\n\n"),
+                Event::End(TagEnd::Item | TagEnd::TableRow | TagEnd::TableHead) => text.push('\n'),
+                Event::End(TagEnd::TableCell) => text.push('\t'),
+                // the html variant keeps code in <code> and <pre>, where placeholders are left alone, the braces
+                // of code are escaped so that the plain text variant leaves them alone too
+                Event::Text(s) if in_code_block => text.push_str(&s.replace("{{", "\\{{")),
+                Event::Code(s) => text.push_str(&s.replace("{{", "\\{{")),
+                Event::Text(s) => text.push_str(&s),
+                Event::SoftBreak | Event::HardBreak => text.push('\n'),
+                Event::Rule => text.push_str("----\n\n"),
+                _ => {}
+            }
+        }
+        // nested lists and items wrapping paragraphs leave runs of blank lines behind
+        let mut collapsed = String::with_capacity(text.len());
+        for line in text.trim().lines() {
+            if line.trim().is_empty() && collapsed.ends_with("\n\n") {
+                continue;
+            }
+            collapsed.push_str(line.trim_end());
+            collapsed.push('\n');
+        }
+        collapsed.trim_end().to_string()
+    }
+}
+
+// impl block to return the raw Markdown source
+impl AsRef<str> for NewsletterMarkdown {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// unit tests for the newsletter markdown type
+#[cfg(test)]
+mod tests {
+    use super::NewsletterMarkdown;
+    use claims::assert_err;
+
+    fn markdown(s: &str) -> NewsletterMarkdown {
+        NewsletterMarkdown::parse(s.to_string()).unwrap()
+    }
+
+    #[test]
+    fn blank_markdown_is_rejected() {
+        for source in ["", "   ", "\n\n"] {
+            assert_err!(NewsletterMarkdown::parse(source.to_string()));
+        }
+    }
+
+    #[test]
+    fn markdown_is_rendered_to_html() {
+        let html =
+            markdown("# Hello\n\nSome *emphasis* and a [link](https://example.com).").to_html();
+        assert!(html.contains("<h1>Hello</h1>"));
+        assert!(html.contains("<em>emphasis</em>"));
+        assert!(html.contains(r#"<a href="https://example.com""#));
+    }
+
+    #[test]
+    fn raw_html_in_markdown_is_sanitized() {
+        let html =
+            markdown("Hi <script>alert('boom')</script> <img src=x onerror=alert(1)>").to_html();
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("onerror"));
+    }
+
+    #[test]
+    fn markdown_is_rendered_to_readable_plain_text() {
+        let text = markdown(
+            "# Hello\n\nSome *emphasis* and a [link](https://example.com).\n\n* one\n* two\n\nBye",
+        )
+        .to_text();
+        assert_eq!(
+            text,
+            "Hello\n\nSome emphasis and a link (https://example.com).\n\n- one\n- two\n\nBye"
+        );
+    }
+
+    #[test]
+    fn placeholders_in_code_are_escaped_in_plain_text() {
+        let text = markdown("Hi {{ name }}, `{{name}}`\n\n```rust\nprintln!(\"{{name}}\");\n```")
+            .to_text();
+        assert_eq!(
+            text,
+            "Hi {{ name }}, \\{{name}}\n\nprintln!(\"\\{{name}}\");"
+        );
+    }
+}
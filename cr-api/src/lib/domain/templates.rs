@@ -37,6 +37,14 @@ pub struct ChangePasswordTemplate {
     pub flash_msg: String,
 }
 
+// struct to represent the admin email address form template
+#[derive(Template)]
+#[template(path = "admin_email_form.html")]
+pub struct AdminEmailTemplate {
+    pub flash_msg: String,
+    pub email: String,
+}
+
 // struct to represent the publish newsletter form template
 #[derive(Template)]
 #[template(path = "publish_newsletter_form.html")]
@@ -45,6 +53,195 @@ pub struct PublishNewsletterTemplate {
     pub idempotency_key: Uuid,
 }
 
+// struct to represent a single row in the list of drafts
+pub struct DraftSummary {
+    pub newsletter_issue_id: Uuid,
+    pub title: String,
+    pub idempotency_key: Uuid,
+}
+
+// struct to represent the list of drafts template
+#[derive(Template)]
+#[template(path = "drafts.html")]
+pub struct DraftsTemplate {
+    pub flash_msg: String,
+    pub drafts: Vec<DraftSummary>,
+}
+
+// struct to represent the create and edit draft form template
+#[derive(Template)]
+#[template(path = "draft_form.html")]
+pub struct DraftFormTemplate {
+    pub flash_msg: String,
+    pub newsletter_issue_id: Option<Uuid>,
+    pub form_action: String,
+    pub title: String,
+    pub text_content: String,
+    pub html_content: String,
+}
+
+// struct to represent the draft preview template
+#[derive(Template)]
+#[template(path = "draft_preview.html")]
+pub struct DraftPreviewTemplate {
+    pub flash_msg: String,
+    pub newsletter_issue_id: Uuid,
+    pub title: String,
+    pub html_content: String,
+}
+
+// struct to represent a single published issue in the public archive
+pub struct ArchivedIssue {
+    pub slug: String,
+    pub title: String,
+    pub published_at: String,
+}
+
+// struct to represent the public archive template
+#[derive(Template)]
+#[template(path = "archive.html")]
+pub struct ArchiveTemplate {
+    pub flash_msg: String,
+    pub issues: Vec<ArchivedIssue>,
+    pub newer_page: Option<i64>,
+    pub older_page: Option<i64>,
+}
+
+// struct to represent the public page of a published issue
+#[derive(Template)]
+#[template(path = "archived_issue.html")]
+pub struct ArchivedIssueTemplate {
+    pub flash_msg: String,
+    pub title: String,
+    pub published_at: String,
+    pub html_content: String,
+}
+
+// struct to represent a single published issue in the feeds, the dates are formatted as each feed requires
+pub struct FeedEntry {
+    pub title: String,
+    pub link: String,
+    pub published_at: String,
+    pub html_content: String,
+}
+
+// struct to represent the RSS feed template
+#[derive(Template)]
+#[template(path = "feeds/rss.xml")]
+pub struct RssFeedTemplate {
+    pub base_url: String,
+    pub last_build_date: Option<String>,
+    pub entries: Vec<FeedEntry>,
+}
+
+// struct to represent the Atom feed template
+#[derive(Template)]
+#[template(path = "feeds/atom.xml")]
+pub struct AtomFeedTemplate {
+    pub base_url: String,
+    pub updated: String,
+    pub entries: Vec<FeedEntry>,
+}
+
+// struct to represent a single recipient row on the newsletter issue delivery status page
+pub struct IssueDelivery {
+    pub subscriber_email: String,
+    pub status: String,
+    pub recorded_at: String,
+    pub error: String,
+}
+
+// struct to represent the newsletter issue delivery status template
+#[derive(Template)]
+#[template(path = "newsletter_issue_status.html")]
+pub struct NewsletterIssueStatusTemplate {
+    pub flash_msg: String,
+    pub newsletter_issue_id: Uuid,
+    pub title: String,
+    pub published_at: String,
+    pub n_sent: usize,
+    pub n_failed: usize,
+    pub n_skipped: usize,
+    pub n_unsubscribed: usize,
+    pub n_pending: usize,
+    pub deliveries: Vec<IssueDelivery>,
+}
+
+// struct to represent a single row of the subscriber list
+pub struct SubscriberRow {
+    pub id: Uuid,
+    pub email: String,
+    pub name: String,
+    pub status: String,
+    pub subscribed_at: String,
+}
+
+// struct to represent an option of the subscriber status filter
+pub struct StatusOption {
+    pub value: &'static str,
+    pub selected: bool,
+}
+
+// struct to represent a single entry of the audit log, as shown below the subscriber list
+pub struct AdminActionRow {
+    pub recorded_at: String,
+    pub username: String,
+    pub action: String,
+    pub subscriber_email: String,
+}
+
+// struct to represent the subscriber list template
+#[derive(Template)]
+#[template(path = "subscribers.html")]
+pub struct SubscribersTemplate {
+    pub flash_msg: String,
+    pub search: String,
+    pub status_options: Vec<StatusOption>,
+    pub n_matching: i64,
+    pub subscribers: Vec<SubscriberRow>,
+    pub newer_page_link: Option<String>,
+    pub older_page_link: Option<String>,
+    pub admin_actions: Vec<AdminActionRow>,
+}
+
+// struct to represent a row of an imported CSV file which wasn't imported, along with the reason why
+pub struct ImportedRowIssue {
+    pub line: u64,
+    pub message: String,
+}
+
+// struct to represent the outcome of a subscriber import
+pub struct ImportReport {
+    pub n_imported: usize,
+    pub imported_as: String,
+    pub skipped: Vec<ImportedRowIssue>,
+    pub errors: Vec<ImportedRowIssue>,
+}
+
+// struct to represent the subscriber import template, the report is only there once a file was imported
+#[derive(Template)]
+#[template(path = "subscribers_import.html")]
+pub struct SubscribersImportTemplate {
+    pub flash_msg: String,
+    pub report: Option<ImportReport>,
+}
+
+// struct to represent a single row of the suppression list
+pub struct Suppression {
+    pub email: String,
+    pub reason: String,
+    pub source: String,
+    pub created_at: String,
+}
+
+// struct to represent the suppression list template
+#[derive(Template)]
+#[template(path = "suppressions.html")]
+pub struct SuppressionsTemplate {
+    pub flash_msg: String,
+    pub suppressions: Vec<Suppression>,
+}
+
 // struct to represent the subscription confirmation template
 #[derive(Template)]
 #[template(path = "subscription_confirmed.html")]
@@ -52,6 +249,20 @@ pub struct SubscriptionConfirmationTemplate {
     pub flash_msg: String,
 }
 
+// struct to represent the page shown for an expired or already used confirmation link
+#[derive(Template)]
+#[template(path = "confirmation_link_expired.html")]
+pub struct ConfirmationLinkExpiredTemplate {
+    pub flash_msg: String,
+}
+
+// struct to represent the resend confirmation email form template
+#[derive(Template)]
+#[template(path = "resend_confirmation_form.html")]
+pub struct ResendConfirmationTemplate {
+    pub flash_msg: String,
+}
+
 // struct to represent the pending subscription confirmation template
 #[derive(Template)]
 #[template(path = "pending_subscription.html")]
@@ -59,6 +270,101 @@ pub struct PendingConfirmationTemplate {
     pub flash_msg: String,
 }
 
+// struct to represent the unsubscribe confirmation form template
+#[derive(Template)]
+#[template(path = "unsubscribe_form.html")]
+pub struct UnsubscribeFormTemplate {
+    pub flash_msg: String,
+    pub token: String,
+}
+
+// struct to represent the unsubscribed template
+#[derive(Template)]
+#[template(path = "unsubscribed.html")]
+pub struct UnsubscribedTemplate {
+    pub flash_msg: String,
+}
+
+// email templates, each email has an html and a plain text variant extending emails/base.html and emails/base.txt
+
+// struct to hold an email body rendered from both variants of its template
+pub struct EmailBody {
+    pub html: String,
+    pub text: String,
+}
+
+impl EmailBody {
+    pub fn render(html: impl Template, text: impl Template) -> Result<Self, askama::Error> {
+        Ok(Self {
+            html: html.render()?,
+            text: text.render()?,
+        })
+    }
+}
+
+// structs to represent the confirmation email templates, sent to new subscribers
+#[derive(Template)]
+#[template(path = "emails/confirmation.html")]
+pub struct ConfirmationEmailHtml<'a> {
+    pub confirmation_link: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "emails/confirmation.txt")]
+pub struct ConfirmationEmailText<'a> {
+    pub confirmation_link: &'a str,
+}
+
+// structs to represent the welcome email templates, sent once a subscription is confirmed
+#[derive(Template)]
+#[template(path = "emails/welcome.html")]
+pub struct WelcomeEmailHtml<'a> {
+    pub unsubscribe_link: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "emails/welcome.txt")]
+pub struct WelcomeEmailText<'a> {
+    pub unsubscribe_link: &'a str,
+}
+
+// structs to represent the already subscribed email templates, sent when a confirmed subscriber signs up again
+#[derive(Template)]
+#[template(path = "emails/already_subscribed.html")]
+pub struct AlreadySubscribedEmailHtml<'a> {
+    pub unsubscribe_link: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "emails/already_subscribed.txt")]
+pub struct AlreadySubscribedEmailText<'a> {
+    pub unsubscribe_link: &'a str,
+}
+
+// structs to represent the unsubscribe confirmation email templates
+#[derive(Template)]
+#[template(path = "emails/unsubscribed.html")]
+pub struct UnsubscribedEmailHtml;
+
+#[derive(Template)]
+#[template(path = "emails/unsubscribed.txt")]
+pub struct UnsubscribedEmailText;
+
+// structs to represent the newsletter issue templates, wrapping the issue content in the header and footer
+#[derive(Template)]
+#[template(path = "emails/issue.html")]
+pub struct IssueEmailHtml<'a> {
+    pub content: &'a str,
+    pub unsubscribe_link: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "emails/issue.txt")]
+pub struct IssueEmailText<'a> {
+    pub content: &'a str,
+    pub unsubscribe_link: &'a str,
+}
+
 // implement IntoResponse for the Askama templates
 pub fn into_response<T: Template>(t: &T) -> Response {
     match t.render() {
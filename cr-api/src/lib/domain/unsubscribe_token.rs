@@ -0,0 +1,100 @@
+// src/lib/domain/unsubscribe_token.rs
+
+// domain unsubscribe token type
+
+// dependencies
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, Secret};
+use sha2::Sha256;
+use uuid::Uuid;
+
+// a struct to represent an unsubscribe token, the subscriber id followed by its HMAC-SHA256 tag
+#[derive(Debug, Clone)]
+pub struct UnsubscribeToken(String);
+
+// impl block for the unsubscribe token type; generates tokens and verifies incoming ones
+impl UnsubscribeToken {
+    pub fn generate(subscriber_id: Uuid, hmac_secret: &Secret<String>) -> Self {
+        let tag = hex::encode(
+            Self::mac(subscriber_id, hmac_secret)
+                .finalize()
+                .into_bytes(),
+        );
+        Self(format!("{}.{}", subscriber_id, tag))
+    }
+
+    // returns the subscriber id embedded in the token, if the tag matches
+    pub fn verify(s: &str, hmac_secret: &Secret<String>) -> Result<Uuid, String> {
+        let (subscriber_id, tag) = s
+            .split_once('.')
+            .ok_or_else(|| format!("{} is not a valid unsubscribe token.", s))?;
+        let subscriber_id = Uuid::parse_str(subscriber_id)
+            .map_err(|_| format!("{} is not a valid unsubscribe token.", s))?;
+        let tag =
+            hex::decode(tag).map_err(|_| format!("{} is not a valid unsubscribe token.", s))?;
+        Self::mac(subscriber_id, hmac_secret)
+            .verify_slice(&tag)
+            .map_err(|_| format!("{} is not a valid unsubscribe token.", s))?;
+        Ok(subscriber_id)
+    }
+
+    fn mac(subscriber_id: Uuid, hmac_secret: &Secret<String>) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(hmac_secret.expose_secret().as_bytes())
+            .expect("HMAC can take a key of any size");
+        mac.update(b"unsubscribe:");
+        mac.update(subscriber_id.as_bytes());
+        mac
+    }
+}
+
+// impl block to return the inner value of the unsubscribe token type
+impl AsRef<str> for UnsubscribeToken {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// unit tests for the unsubscribe token type
+#[cfg(test)]
+mod tests {
+    use super::UnsubscribeToken;
+    use claims::{assert_err, assert_ok_eq};
+    use secrecy::Secret;
+    use uuid::Uuid;
+
+    fn secret() -> Secret<String> {
+        Secret::new("a-very-secret-key".to_string())
+    }
+
+    #[test]
+    fn a_generated_token_is_verified_successfully() {
+        let subscriber_id = Uuid::new_v4();
+        let token = UnsubscribeToken::generate(subscriber_id, &secret());
+        assert_ok_eq!(
+            UnsubscribeToken::verify(token.as_ref(), &secret()),
+            subscriber_id
+        );
+    }
+
+    #[test]
+    fn a_token_signed_with_another_secret_is_rejected() {
+        let other_secret = Secret::new("another-secret-key".to_string());
+        let token = UnsubscribeToken::generate(Uuid::new_v4(), &other_secret);
+        assert_err!(UnsubscribeToken::verify(token.as_ref(), &secret()));
+    }
+
+    #[test]
+    fn a_token_for_another_subscriber_is_rejected() {
+        let token = UnsubscribeToken::generate(Uuid::new_v4(), &secret());
+        let (_, tag) = token.as_ref().split_once('.').unwrap();
+        let forged = format!("{}.{}", Uuid::new_v4(), tag);
+        assert_err!(UnsubscribeToken::verify(&forged, &secret()));
+    }
+
+    #[test]
+    fn malformed_tokens_are_rejected() {
+        for token in ["", "not-a-token", "not-a-uuid.abcdef", "."] {
+            assert_err!(UnsubscribeToken::verify(token, &secret()));
+        }
+    }
+}
@@ -1,15 +1,158 @@
 // src/email_client.rs
 
 use crate::domain::SubscriberEmail;
+use crate::rate_limiter::RateLimiter;
+use anyhow::Context;
+use async_trait::async_trait;
+use lettre::message::header::{HeaderName, HeaderValue};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
 use reqwest::Client;
 use secrecy::{ExposeSecret, Secret};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// the most messages Postmark accepts in a single batch request
+const POSTMARK_MAX_BATCH_SIZE: usize = 500;
+
+// a struct to represent an email to a single recipient, as handed over to a transport
+#[derive(Debug)]
+pub struct Email<'a> {
+    pub recipient: &'a SubscriberEmail,
+    pub subject: &'a str,
+    pub html_content: &'a str,
+    pub text_content: &'a str,
+    pub headers: &'a [EmailHeader<'a>],
+}
+
+// a struct to represent a custom header attached to an outgoing email
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct EmailHeader<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+}
+
+// a trait for the ways an email can leave the application
+#[async_trait]
+pub trait EmailTransport: std::fmt::Debug + Send + Sync {
+    async fn send(&self, sender: &SubscriberEmail, email: &Email<'_>) -> Result<(), anyhow::Error>;
+
+    // sends several emails, returning one result per email in the same order
+    // transports without a batch API send them one at a time
+    async fn send_batch(
+        &self,
+        sender: &SubscriberEmail,
+        emails: &[Email<'_>],
+    ) -> Vec<Result<(), anyhow::Error>> {
+        let mut results = Vec::with_capacity(emails.len());
+        for email in emails {
+            results.push(self.send(sender, email).await);
+        }
+        results
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct EmailClient {
+    transport: Arc<dyn EmailTransport>,
+    sender: SubscriberEmail,
+    // shared by every clone of the client, so that all workers draw from the same limits
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl EmailClient {
+    // returns an email client which sends through the Postmark API
+    pub fn new(
+        base_url: String,
+        sender: SubscriberEmail,
+        authorization_token: Secret<String>,
+        timeout: std::time::Duration,
+    ) -> Self {
+        Self::with_transport(
+            sender,
+            PostmarkTransport::new(base_url, authorization_token, timeout),
+        )
+    }
+
+    pub fn with_transport(
+        sender: SubscriberEmail,
+        transport: impl EmailTransport + 'static,
+    ) -> Self {
+        Self {
+            transport: Arc::new(transport),
+            sender,
+            rate_limiter: None,
+        }
+    }
+
+    // returns the client with every outgoing message subject to the given rate limit
+    pub fn with_rate_limiter(self, rate_limiter: RateLimiter) -> Self {
+        Self {
+            rate_limiter: Some(Arc::new(rate_limiter)),
+            ..self
+        }
+    }
+
+    // waits until the rate limit allows another `n_messages` messages to go out
+    // the time spent waiting is recorded on the `throttled_milliseconds` field of the calling span, if it has one
+    async fn throttle(&self, n_messages: usize) {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            let mut throttled = std::time::Duration::ZERO;
+            for _ in 0..n_messages {
+                throttled += rate_limiter.acquire().await;
+            }
+            if !throttled.is_zero() {
+                tracing::Span::current()
+                    .record("throttled_milliseconds", throttled.as_millis() as u64);
+            }
+        }
+    }
+
+    pub async fn send_email(
+        &self,
+        recipient: &SubscriberEmail,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+    ) -> Result<(), anyhow::Error> {
+        self.send_email_with_headers(recipient, subject, html_content, text_content, &[])
+            .await
+    }
+
+    pub async fn send_email_with_headers(
+        &self,
+        recipient: &SubscriberEmail,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+        headers: &[EmailHeader<'_>],
+    ) -> Result<(), anyhow::Error> {
+        let email = Email {
+            recipient,
+            subject,
+            html_content,
+            text_content,
+            headers,
+        };
+        self.throttle(1).await;
+        self.transport.send(&self.sender, &email).await
+    }
+
+    // sends several emails at once, returning one result per email in the same order
+    pub async fn send_email_batch(&self, emails: &[Email<'_>]) -> Vec<Result<(), anyhow::Error>> {
+        self.throttle(emails.len()).await;
+        self.transport.send_batch(&self.sender, emails).await
+    }
+}
+
+// the Postmark transport, sends emails through Postmark's `/email` JSON API
+#[derive(Debug)]
+pub struct PostmarkTransport {
     http_client: Client,
     base_url: String,
-    sender: SubscriberEmail,
     authorization_token: Secret<String>,
 }
 
@@ -21,12 +164,34 @@ struct SendEmailRequest<'a> {
     subject: &'a str,
     html_body: &'a str,
     text_body: &'a str,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    headers: &'a [EmailHeader<'a>],
 }
 
-impl EmailClient {
+// a struct to represent Postmark's verdict on a single message of a batch request
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BatchResponseEntry {
+    error_code: i64,
+    message: String,
+}
+
+impl<'a> SendEmailRequest<'a> {
+    fn new(sender: &'a SubscriberEmail, email: &'a Email<'a>) -> Self {
+        Self {
+            from: sender.as_ref(),
+            to: email.recipient.as_ref(),
+            subject: email.subject,
+            html_body: email.html_content,
+            text_body: email.text_content,
+            headers: email.headers,
+        }
+    }
+}
+
+impl PostmarkTransport {
     pub fn new(
         base_url: String,
-        sender: SubscriberEmail,
         authorization_token: Secret<String>,
         timeout: std::time::Duration,
     ) -> Self {
@@ -34,26 +199,16 @@ impl EmailClient {
         Self {
             http_client,
             base_url,
-            sender,
             authorization_token,
         }
     }
+}
 
-    pub async fn send_email(
-        &self,
-        recipient: &SubscriberEmail,
-        subject: &str,
-        html_content: &str,
-        text_content: &str,
-    ) -> Result<(), reqwest::Error> {
+#[async_trait]
+impl EmailTransport for PostmarkTransport {
+    async fn send(&self, sender: &SubscriberEmail, email: &Email<'_>) -> Result<(), anyhow::Error> {
         let url = format!("{}/email", self.base_url);
-        let request_body = SendEmailRequest {
-            from: self.sender.as_ref(),
-            to: recipient.as_ref(),
-            subject,
-            html_body: html_content,
-            text_body: text_content,
-        };
+        let request_body = SendEmailRequest::new(sender, email);
         let _builder = self
             .http_client
             .post(&url)
@@ -67,12 +222,181 @@ impl EmailClient {
             .error_for_status()?;
         Ok(())
     }
+
+    async fn send_batch(
+        &self,
+        sender: &SubscriberEmail,
+        emails: &[Email<'_>],
+    ) -> Vec<Result<(), anyhow::Error>> {
+        // a lone email goes through the regular endpoint, there is nothing to batch
+        if let [email] = emails {
+            return vec![self.send(sender, email).await];
+        }
+        let mut results = Vec::with_capacity(emails.len());
+        for chunk in emails.chunks(POSTMARK_MAX_BATCH_SIZE) {
+            match self.send_chunk(sender, chunk).await {
+                Ok(chunk_results) => results.extend(chunk_results),
+                // the whole request failed, so did every message in it
+                Err(e) => results.extend(
+                    chunk
+                        .iter()
+                        .map(|_| Err(anyhow::anyhow!("The batch request failed: {:#}", e))),
+                ),
+            }
+        }
+        results
+    }
+}
+
+impl PostmarkTransport {
+    // sends up to POSTMARK_MAX_BATCH_SIZE emails through Postmark's `/email/batch` JSON API
+    async fn send_chunk(
+        &self,
+        sender: &SubscriberEmail,
+        emails: &[Email<'_>],
+    ) -> Result<Vec<Result<(), anyhow::Error>>, anyhow::Error> {
+        let url = format!("{}/email/batch", self.base_url);
+        let request_body: Vec<_> = emails
+            .iter()
+            .map(|email| SendEmailRequest::new(sender, email))
+            .collect();
+        let response: Vec<BatchResponseEntry> = self
+            .http_client
+            .post(&url)
+            .header(
+                "X-Postmark-Server-Token",
+                self.authorization_token.expose_secret(),
+            )
+            .json(&request_body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Failed to parse the batch response.")?;
+        if response.len() != emails.len() {
+            anyhow::bail!(
+                "Expected {} results in the batch response, got {}.",
+                emails.len(),
+                response.len()
+            );
+        }
+        Ok(response
+            .into_iter()
+            .map(|entry| match entry.error_code {
+                0 => Ok(()),
+                error_code => Err(anyhow::anyhow!(
+                    "Postmark rejected the message with error code {}: {}",
+                    error_code,
+                    entry.message
+                )),
+            })
+            .collect())
+    }
+}
+
+// function to build a multipart (plain text and html) MIME message, shared by the SMTP and file transports
+fn to_message(sender: &SubscriberEmail, email: &Email<'_>) -> Result<Message, anyhow::Error> {
+    let mut builder = Message::builder()
+        .from(sender.as_ref().parse::<Mailbox>()?)
+        .to(email.recipient.as_ref().parse::<Mailbox>()?)
+        .subject(email.subject);
+    for header in email.headers {
+        let name = HeaderName::new_from_ascii(header.name.to_string())
+            .with_context(|| format!("{} is not a valid header name.", header.name))?;
+        builder = builder.raw_header(HeaderValue::new(name, header.value.to_string()));
+    }
+    let message = builder.multipart(MultiPart::alternative_plain_html(
+        email.text_content.to_string(),
+        email.html_content.to_string(),
+    ))?;
+    Ok(message)
+}
+
+// the SMTP transport, hands emails over to an SMTP relay
+#[derive(Debug)]
+pub struct SmtpTransport {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    // without TLS the connection is in plain text, which is only meant for local relays such as a mail catcher
+    pub fn new(
+        host: &str,
+        port: u16,
+        credentials: Option<(String, Secret<String>)>,
+        require_tls: bool,
+        timeout: std::time::Duration,
+    ) -> Result<Self, anyhow::Error> {
+        let mut builder = if require_tls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+                .context("Failed to set up the TLS connection to the SMTP relay.")?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+        };
+        builder = builder.port(port).timeout(Some(timeout));
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(
+                username,
+                password.expose_secret().to_string(),
+            ));
+        }
+        Ok(Self {
+            mailer: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl EmailTransport for SmtpTransport {
+    async fn send(&self, sender: &SubscriberEmail, email: &Email<'_>) -> Result<(), anyhow::Error> {
+        let message = to_message(sender, email)?;
+        self.mailer
+            .send(message)
+            .await
+            .context("The SMTP relay rejected the email.")?;
+        Ok(())
+    }
+}
+
+// the file transport, writes every email as an .eml file into a directory, for development and tests
+#[derive(Debug)]
+pub struct FileTransport {
+    directory: PathBuf,
+    mailer: AsyncFileTransport<Tokio1Executor>,
+}
+
+impl FileTransport {
+    pub fn new(directory: PathBuf) -> Result<Self, anyhow::Error> {
+        std::fs::create_dir_all(&directory).with_context(|| {
+            format!(
+                "Failed to create the email directory {}.",
+                directory.display()
+            )
+        })?;
+        Ok(Self {
+            mailer: AsyncFileTransport::new(&directory),
+            directory,
+        })
+    }
+}
+
+#[async_trait]
+impl EmailTransport for FileTransport {
+    async fn send(&self, sender: &SubscriberEmail, email: &Email<'_>) -> Result<(), anyhow::Error> {
+        let message = to_message(sender, email)?;
+        let email_id = self.mailer.send(message).await.with_context(|| {
+            format!("Failed to write the email to {}.", self.directory.display())
+        })?;
+        tracing::info!(email_id, "Email written to {}.", self.directory.display());
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::domain::SubscriberEmail;
-    use crate::email_client::EmailClient;
+    use crate::email_client::{Email, EmailClient, EmailHeader, FileTransport};
     use claims::{assert_err, assert_ok};
     use fake::faker::internet::en::SafeEmail;
     use fake::faker::lorem::en::{Paragraph, Sentence};
@@ -145,6 +469,42 @@ mod tests {
         // Assert
     }
 
+    #[tokio::test]
+    async fn send_email_with_headers_includes_the_headers_in_the_request() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+
+        Mock::given(path("/email"))
+            .and(method("POST"))
+            .and(SendEmailBodyMatcher)
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let headers = [EmailHeader {
+            name: "List-Unsubscribe-Post",
+            value: "List-Unsubscribe=One-Click",
+        }];
+        let outcome = email_client
+            .send_email_with_headers(&email(), &subject(), &content(), &content(), &headers)
+            .await;
+
+        // Assert
+        assert_ok!(outcome);
+        let request = &mock_server.received_requests().await.unwrap()[0];
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(
+            body["Headers"],
+            serde_json::json!([{
+                "Name": "List-Unsubscribe-Post",
+                "Value": "List-Unsubscribe=One-Click"
+            }])
+        );
+    }
+
     #[tokio::test]
     async fn send_email_succeeds_if_the_server_returns_200() {
         // Arrange
@@ -206,4 +566,141 @@ mod tests {
         // Assert
         assert_err!(outcome);
     }
+
+    #[tokio::test]
+    async fn send_email_batch_sends_a_single_batch_request_and_maps_the_results() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+        let recipients = [email(), email(), email()];
+        let (subject, content) = (subject(), content());
+        let emails: Vec<_> = recipients
+            .iter()
+            .map(|recipient| Email {
+                recipient,
+                subject: &subject,
+                html_content: &content,
+                text_content: &content,
+                headers: &[],
+            })
+            .collect();
+
+        Mock::given(header_exists("X-Postmark-Server-Token"))
+            .and(path("/email/batch"))
+            .and(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                { "ErrorCode": 0, "Message": "OK" },
+                { "ErrorCode": 406, "Message": "Inactive recipient" },
+                { "ErrorCode": 0, "Message": "OK" }
+            ])))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let results = email_client.send_email_batch(&emails).await;
+
+        // Assert
+        assert_eq!(results.len(), 3);
+        assert_ok!(&results[0]);
+        assert_err!(&results[1]);
+        assert_ok!(&results[2]);
+        let request = &mock_server.received_requests().await.unwrap()[0];
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body.as_array().unwrap().len(), 3);
+        assert_eq!(body[1]["To"], recipients[1].as_ref());
+    }
+
+    #[tokio::test]
+    async fn send_email_batch_fails_every_message_if_the_server_returns_500() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+        let recipients = [email(), email()];
+        let (subject, content) = (subject(), content());
+        let emails: Vec<_> = recipients
+            .iter()
+            .map(|recipient| Email {
+                recipient,
+                subject: &subject,
+                html_content: &content,
+                text_content: &content,
+                headers: &[],
+            })
+            .collect();
+
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let results = email_client.send_email_batch(&emails).await;
+
+        // Assert
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(Result::is_err));
+    }
+
+    #[tokio::test]
+    async fn file_transport_writes_the_email_as_an_eml_file() {
+        // Arrange
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let transport = FileTransport::new(directory.clone()).unwrap();
+        let email_client = EmailClient::with_transport(email(), transport);
+        let recipient = email();
+
+        // Act
+        let headers = [EmailHeader {
+            name: "List-Unsubscribe-Post",
+            value: "List-Unsubscribe=One-Click",
+        }];
+        let outcome = email_client
+            .send_email_with_headers(
+                &recipient,
+                "Newsletter title",
+                "<p>Newsletter body as HTML</p>",
+                "Newsletter body as plain text",
+                &headers,
+            )
+            .await;
+
+        // Assert
+        assert_ok!(outcome);
+        let files: Vec<_> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].extension().unwrap(), "eml");
+        let eml = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(eml.contains(&format!("To: {}", recipient.as_ref())));
+        assert!(eml.contains("Subject: Newsletter title"));
+        assert!(eml.contains("List-Unsubscribe-Post: List-Unsubscribe=One-Click"));
+        assert!(eml.contains("<p>Newsletter body as HTML</p>"));
+        assert!(eml.contains("Newsletter body as plain text"));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn file_transport_rejects_invalid_header_names() {
+        // Arrange
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let transport = FileTransport::new(directory.clone()).unwrap();
+        let email_client = EmailClient::with_transport(email(), transport);
+
+        // Act
+        let headers = [EmailHeader {
+            name: "Not a header",
+            value: "value",
+        }];
+        let outcome = email_client
+            .send_email_with_headers(&email(), &subject(), &content(), &content(), &headers)
+            .await;
+
+        // Assert
+        assert_err!(outcome);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
 }
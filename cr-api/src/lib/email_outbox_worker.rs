@@ -0,0 +1,373 @@
+// src/lib/email_outbox_worker.rs
+
+// dependencies
+use crate::configuration::EmailOutboxSettings;
+use crate::domain::SubscriberEmail;
+use crate::email_client::EmailClient;
+use crate::issue_delivery_worker::{
+    backoff_delay, while_claimed, ExecutionOutcome, CLAIM_DURATION,
+};
+use crate::shutdown::Shutdown;
+use crate::suppressions::blocks_transactional_email;
+use crate::{configuration::Settings, startup::get_connection_pool};
+use chrono::Utc;
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tracing::Span;
+use uuid::Uuid;
+
+// type declaration
+type PgTransaction = Transaction<'static, Postgres>;
+
+// a struct to represent an email taken from the outbox
+struct OutboxEmail {
+    id: Uuid,
+    recipient: String,
+    subject: String,
+    html_content: String,
+    text_content: String,
+    n_attempts: i32,
+}
+
+// function to run the email outbox worker until stopped, the email client is shared with the other senders
+// so that they all draw from the same email rate limit
+pub async fn run_outbox_until_stopped(
+    configuration: Settings,
+    email_client: EmailClient,
+    shutdown: Shutdown,
+) -> Result<(), anyhow::Error> {
+    anyhow::ensure!(
+        configuration.email_outbox.batch_size > 0,
+        "The email outbox batch size must be at least 1."
+    );
+    let connection_pool = get_connection_pool(&configuration.database);
+    worker_loop(
+        connection_pool,
+        email_client,
+        configuration.email_outbox,
+        shutdown,
+    )
+    .await
+}
+
+// function to run the email outbox worker in a loop, until shutdown is requested
+async fn worker_loop(
+    pool: PgPool,
+    email_client: EmailClient,
+    settings: EmailOutboxSettings,
+    mut shutdown: Shutdown,
+) -> Result<(), anyhow::Error> {
+    while !shutdown.is_requested() {
+        let wait_time = match try_send_outbox_emails(&pool, &email_client, &settings).await {
+            Ok(ExecutionOutcome::EmptyQueue) => Duration::from_secs(1),
+            Err(_) => Duration::from_secs(1),
+            Ok(ExecutionOutcome::TaskCompleted) => continue,
+        };
+        shutdown.sleep(wait_time).await;
+    }
+    tracing::info!("Stopped sending outbox emails");
+    Ok(())
+}
+
+// function to write an email to the outbox, as part of the transaction making the change it is about
+#[tracing::instrument(skip(transaction, html_content, text_content))]
+pub async fn enqueue_email(
+    transaction: &mut Transaction<'_, Postgres>,
+    recipient: &SubscriberEmail,
+    subject: &str,
+    html_content: &str,
+    text_content: &str,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO email_outbox (
+            id,
+            recipient,
+            subject,
+            html_content,
+            text_content,
+            created_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        Uuid::new_v4(),
+        recipient.as_ref(),
+        subject,
+        html_content,
+        text_content,
+        Utc::now()
+    );
+    transaction.execute(query).await?;
+    Ok(())
+}
+
+// function to send a chunk of due emails from the outbox, each one is sent and settled on its own so that failing
+// to record one email doesn't send the rest of the chunk again
+#[tracing::instrument(skip_all, fields(n_emails = tracing::field::Empty), err)]
+pub async fn try_send_outbox_emails(
+    pool: &PgPool,
+    email_client: &EmailClient,
+    settings: &EmailOutboxSettings,
+) -> Result<ExecutionOutcome, anyhow::Error> {
+    let emails = claim_emails(pool, settings.batch_size).await?;
+    if emails.is_empty() {
+        return Ok(ExecutionOutcome::EmptyQueue);
+    }
+    Span::current().record("n_emails", emails.len());
+
+    // the claim on the emails yet to be settled is renewed for as long as the chunk is being sent
+    let ids: Vec<_> = emails.iter().map(|email| email.id).collect();
+    let n_settled = AtomicUsize::new(0);
+    let send_all = async {
+        let mut settled = Ok(());
+        for email in &emails {
+            let outcome = send_outbox_email(pool, email_client, email).await;
+            settled = settled.and(settle_email(pool, email, outcome, settings).await);
+            n_settled.fetch_add(1, Ordering::Relaxed);
+        }
+        settled
+    };
+    while_claimed(send_all, || {
+        renew_claim(pool, &ids[n_settled.load(Ordering::Relaxed)..])
+    })
+    .await?;
+    Ok(ExecutionOutcome::TaskCompleted)
+}
+
+// an enum to represent what happened to a claimed email
+enum EmailOutcome {
+    Sent,
+    Suppressed,
+    Failed(anyhow::Error),
+}
+
+// function to send a single email from the outbox, unless its recipient was suppressed after it was queued; an
+// unsubscribe still gets its confirmation through
+async fn send_outbox_email(
+    pool: &PgPool,
+    email_client: &EmailClient,
+    email: &OutboxEmail,
+) -> EmailOutcome {
+    let recipient = match SubscriberEmail::parse(email.recipient.clone()) {
+        Ok(recipient) => recipient,
+        Err(e) => return EmailOutcome::Failed(anyhow::anyhow!(e)),
+    };
+    match blocks_transactional_email(pool, recipient.as_ref()).await {
+        Ok(true) => return EmailOutcome::Suppressed,
+        Ok(false) => {}
+        Err(e) => return EmailOutcome::Failed(e.into()),
+    }
+    let sent = email_client
+        .send_email(
+            &recipient,
+            &email.subject,
+            &email.html_content,
+            &email.text_content,
+        )
+        .await;
+    match sent {
+        Ok(()) => EmailOutcome::Sent,
+        Err(e) => EmailOutcome::Failed(e),
+    }
+}
+
+// function to record the outcome of a single email in its own transaction; an error is logged, the email is then
+// left to be picked up again once its claim has elapsed
+async fn settle_email(
+    pool: &PgPool,
+    email: &OutboxEmail,
+    outcome: EmailOutcome,
+    settings: &EmailOutboxSettings,
+) -> Result<(), anyhow::Error> {
+    let settled = async {
+        let mut transaction = pool.begin().await?;
+        match outcome {
+            EmailOutcome::Sent => delete_email(&mut transaction, email).await?,
+            EmailOutcome::Suppressed => {
+                tracing::warn!(
+                    outbox_email_id = %email.id,
+                    recipient = %email.recipient,
+                    "Dropping an outbox email, its recipient is suppressed."
+                );
+                delete_email(&mut transaction, email).await?
+            }
+            EmailOutcome::Failed(e) => {
+                handle_failed_email(&mut transaction, email, &e, settings).await?
+            }
+        }
+        transaction.commit().await?;
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+    if let Err(e) = &settled {
+        tracing::error!(
+            outbox_email_id = %email.id,
+            recipient = %email.recipient,
+            error.cause_chain = ?e,
+            error.message = %e,
+            "Failed to record the outcome of an outbox email.",
+        );
+    }
+    settled
+}
+
+// function to either reschedule a failed email or, once out of retries, mark it as failed for good
+async fn handle_failed_email(
+    transaction: &mut PgTransaction,
+    email: &OutboxEmail,
+    e: &anyhow::Error,
+    settings: &EmailOutboxSettings,
+) -> Result<(), anyhow::Error> {
+    let n_attempts = email.n_attempts + 1;
+    if n_attempts as u32 > settings.max_retries {
+        tracing::error!(
+            outbox_email_id = %email.id,
+            recipient = %email.recipient,
+            error.cause_chain = ?e,
+            error.message = %e,
+            n_attempts,
+            "Failed to send an outbox email. Giving up.",
+        );
+        fail_email(transaction, email, n_attempts, &e.to_string()).await
+    } else {
+        let delay = backoff_delay(
+            n_attempts as u32,
+            settings.retry_base_delay(),
+            settings.retry_max_delay(),
+        );
+        tracing::warn!(
+            outbox_email_id = %email.id,
+            recipient = %email.recipient,
+            error.cause_chain = ?e,
+            error.message = %e,
+            n_attempts,
+            retry_in_milliseconds = delay.as_millis() as u64,
+            "Failed to send an outbox email. Scheduling a retry.",
+        );
+        reschedule_email(transaction, email, n_attempts, &e.to_string(), delay).await
+    }
+}
+
+// function to claim a chunk of due emails, their next attempt is pushed back by the claim duration so that no
+// other worker picks them up while they are being sent; the rows are only locked while claiming them
+#[tracing::instrument(skip_all)]
+async fn claim_emails(pool: &PgPool, batch_size: u32) -> Result<Vec<OutboxEmail>, anyhow::Error> {
+    let emails = sqlx::query_as!(
+        OutboxEmail,
+        r#"
+    WITH claimed AS (
+        UPDATE email_outbox
+        SET next_attempt_at = now() + make_interval(secs => $2)
+        WHERE id IN (
+            SELECT id
+            FROM email_outbox
+            WHERE failed_at IS NULL AND next_attempt_at <= now()
+            ORDER BY next_attempt_at
+            FOR UPDATE
+            SKIP LOCKED
+            LIMIT $1
+        )
+        RETURNING id, recipient, subject, html_content, text_content, n_attempts, created_at
+    )
+    SELECT id, recipient, subject, html_content, text_content, n_attempts
+    FROM claimed
+    ORDER BY created_at
+    "#,
+        i64::from(batch_size),
+        CLAIM_DURATION.as_secs_f64()
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(emails)
+}
+
+// function to push the claim on the given emails back by another claim duration, an email locked by the
+// transaction settling it is left alone rather than waited on
+#[tracing::instrument(skip_all)]
+async fn renew_claim(pool: &PgPool, ids: &[Uuid]) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+UPDATE email_outbox
+SET next_attempt_at = now() + make_interval(secs => $2)
+WHERE id IN (
+    SELECT id
+    FROM email_outbox
+    WHERE id = ANY($1) AND failed_at IS NULL
+    FOR UPDATE
+    SKIP LOCKED
+)
+"#,
+        ids,
+        CLAIM_DURATION.as_secs_f64()
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// function to push a failed email back into the outbox, to be picked up again once the delay has elapsed
+#[tracing::instrument(skip_all)]
+async fn reschedule_email(
+    transaction: &mut PgTransaction,
+    email: &OutboxEmail,
+    n_attempts: i32,
+    last_error: &str,
+    delay: Duration,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+UPDATE email_outbox
+SET
+n_attempts = $2,
+last_error = $3,
+next_attempt_at = now() + make_interval(secs => $4)
+WHERE id = $1
+"#,
+        email.id,
+        n_attempts,
+        last_error,
+        delay.as_secs_f64()
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+// function to mark an email which has exhausted its retries as failed, it is kept for inspection
+#[tracing::instrument(skip_all)]
+async fn fail_email(
+    transaction: &mut PgTransaction,
+    email: &OutboxEmail,
+    n_attempts: i32,
+    last_error: &str,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+UPDATE email_outbox
+SET
+n_attempts = $2,
+last_error = $3,
+failed_at = now()
+WHERE id = $1
+"#,
+        email.id,
+        n_attempts,
+        last_error
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+// function to delete a sent email from the outbox
+#[tracing::instrument(skip_all)]
+async fn delete_email(
+    transaction: &mut PgTransaction,
+    email: &OutboxEmail,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(r#"DELETE FROM email_outbox WHERE id = $1"#, email.id)
+        .execute(&mut **transaction)
+        .await?;
+    Ok(())
+}
@@ -1,6 +1,7 @@
 // src/lib/errors.rs
 
 // dependencies
+use crate::domain::ConfirmationLinkExpiredTemplate;
 use axum::{
     http::{HeaderValue, StatusCode},
     response::{IntoResponse, Response},
@@ -64,13 +65,15 @@ impl std::error::Error for StoreTokenError {
     }
 }
 
-// enum to represent a confirmation error, has two variants,Unexpected Error and UnknownToken
+// enum to represent a confirmation error, has three variants, UnexpectedError, UnknownToken and ExpiredToken
 #[derive(thiserror::Error)]
 pub enum ConfirmationError {
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
     #[error("There is no subscriber associated with the provided token.")]
     UnknownToken,
+    #[error("The provided token has expired or was already used.")]
+    ExpiredToken,
 }
 
 // implement the Debug trait for the confirmation error type
@@ -86,6 +89,44 @@ impl IntoResponse for ConfirmationError {
         tracing::error!("{:?}", self);
         let (status, msg) = match self {
             Self::UnknownToken => (StatusCode::UNAUTHORIZED, "unauthorized"),
+            // a stale link is an everyday occurrence, the subscriber gets a page explaining what to do next
+            Self::ExpiredToken => {
+                let expired_template = ConfirmationLinkExpiredTemplate {
+                    flash_msg: String::new(),
+                };
+                return (StatusCode::GONE, expired_template).into_response();
+            }
+            Self::UnexpectedError(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
+            }
+        };
+
+        (status, msg).into_response()
+    }
+}
+
+// enum to represent an unsubscribe error, has two variants, InvalidToken and UnexpectedError
+#[derive(thiserror::Error)]
+pub enum UnsubscribeError {
+    #[error("The provided unsubscribe token is not valid.")]
+    InvalidToken(#[source] anyhow::Error),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+// implement the Debug trait for the unsubscribe error type
+impl std::fmt::Debug for UnsubscribeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+// implement the IntoResponse trait for the unsubscribe error type
+impl IntoResponse for UnsubscribeError {
+    fn into_response(self) -> Response {
+        tracing::error!("{:?}", self);
+        let (status, msg) = match self {
+            Self::InvalidToken(_) => (StatusCode::UNAUTHORIZED, "unauthorized"),
             Self::UnexpectedError(_) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
             }
@@ -129,6 +170,43 @@ impl IntoResponse for PublishError {
     }
 }
 
+// enum to represent a webhook error
+#[derive(thiserror::Error)]
+pub enum WebhookError {
+    #[error("Authentication failed")]
+    AuthError(#[source] anyhow::Error),
+    #[error("{0}")]
+    InvalidPayload(#[source] anyhow::Error),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+// implement the Debug trait for the webhook error type
+impl std::fmt::Debug for WebhookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+// implement the IntoResponse trait for the webhook error type
+impl IntoResponse for WebhookError {
+    fn into_response(self) -> Response {
+        tracing::error!("{:?}", self);
+        match self {
+            WebhookError::InvalidPayload(_) => StatusCode::BAD_REQUEST.into_response(),
+            WebhookError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            WebhookError::AuthError(_) => {
+                let mut response = StatusCode::UNAUTHORIZED.into_response();
+                let header_value = HeaderValue::from_str(r#"Basic realm="webhooks""#).unwrap();
+                response
+                    .headers_mut()
+                    .insert(header::WWW_AUTHENTICATE, header_value);
+                response
+            }
+        }
+    }
+}
+
 // enum to represent an authentication error
 #[derive(thiserror::Error, Debug)]
 pub enum AuthError {
@@ -243,6 +321,17 @@ where
     ResponseError::from(e).set_status(StatusCode::BAD_REQUEST)
 }
 
+// return a 404 with the user-representation of the error as body.
+// the error root cause is preserved for logging purposes
+pub fn e404<T>(e: T) -> ResponseError
+where
+    T: std::fmt::Debug,
+    T: std::fmt::Display + 'static,
+    T: Into<Box<dyn std::error::Error>>,
+{
+    ResponseError::from(e).set_status(StatusCode::NOT_FOUND)
+}
+
 // return a 500 with the user-representation of the validation error as body.
 // the error root cause is preserved for logging purposes
 pub fn e500<T>(e: T) -> ResponseError
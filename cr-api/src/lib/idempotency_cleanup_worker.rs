@@ -1,22 +1,29 @@
 // src/lib/idempotency_cleanup_worker.rs
 
 // dependencies
+use crate::shutdown::Shutdown;
 use crate::{configuration::Settings, startup::get_connection_pool};
 use sqlx::PgPool;
 use std::time::Duration;
 
 // function to run the idempotency cleanup worker until stopped
-pub async fn run_cleanup_until_stopped(configuration: Settings) -> Result<(), anyhow::Error> {
+pub async fn run_cleanup_until_stopped(
+    configuration: Settings,
+    shutdown: Shutdown,
+) -> Result<(), anyhow::Error> {
     let connection_pool = get_connection_pool(&configuration.database);
-    worker_loop(connection_pool).await
+    worker_loop(connection_pool, shutdown).await
 }
 
-// function to run the idempotency cleanup worker in a loop
-async fn worker_loop(pool: PgPool) -> Result<(), anyhow::Error> {
-    loop {
+// function to run the idempotency cleanup worker in a loop, until shutdown is requested
+async fn worker_loop(pool: PgPool, mut shutdown: Shutdown) -> Result<(), anyhow::Error> {
+    while !shutdown.is_requested() {
         remove_old_idempotency_keys(&pool).await?;
-        tokio::time::sleep(Duration::from_secs(60 * 60 * 24)).await;
+        remove_stale_subscriptions(&pool).await?;
+        remove_old_resend_requests(&pool).await?;
+        shutdown.sleep(Duration::from_secs(60 * 60 * 24)).await;
     }
+    Ok(())
 }
 
 // function to remove old idempotency keys out of the associated database
@@ -34,3 +41,51 @@ pub async fn remove_old_idempotency_keys(pool: &PgPool) -> Result<(), sqlx::Erro
     .await?;
     Ok(())
 }
+
+// function to purge long expired confirmation tokens, together with the subscribers who never confirmed,
+// out of the associated database; used tokens are kept as long so that reused links still get a friendly page
+#[tracing::instrument(skip_all)]
+pub async fn remove_stale_subscriptions(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM subscription_tokens
+        WHERE
+            created_at < now() - interval '7 days'
+        "#,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    // a pending subscriber without a token left can't ever confirm
+    sqlx::query!(
+        r#"
+        DELETE FROM subscriptions
+        WHERE
+            status = 'pending_confirmation' AND
+            subscribed_at < now() - interval '7 days' AND
+            NOT EXISTS (
+                SELECT 1 FROM subscription_tokens
+                WHERE subscription_tokens.subscriber_id = subscriptions.id
+            )
+        "#,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(())
+}
+
+// function to remove resend requests older than the throttling window out of the associated database
+#[tracing::instrument(skip_all)]
+pub async fn remove_old_resend_requests(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM confirmation_resend_requests
+        WHERE
+            requested_at < now() - interval '1 day'
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
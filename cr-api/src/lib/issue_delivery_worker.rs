@@ -1,140 +1,746 @@
 // src/lib/issue_delivery_worker.rs
 
 // dependencies
-use crate::domain::SubscriberEmail;
-use crate::email_client::EmailClient;
+use crate::configuration::IssueDeliverySettings;
+use crate::domain::{
+    EmailBody, IssueEmailHtml, IssueEmailText, Personalization, SubscriberEmail, UnsubscribeToken,
+};
+use crate::email_client::{Email, EmailClient, EmailHeader};
+use crate::shutdown::Shutdown;
+use crate::state::{ApplicationBaseUrl, HmacSecret};
 use crate::{configuration::Settings, startup::get_connection_pool};
-use sqlx::{PgPool, Postgres, Transaction};
+use rand::Rng;
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use std::collections::hash_map::{Entry, HashMap};
+use std::future::Future;
 use std::time::Duration;
-use tracing::{field::display, Span};
+use tokio::time::{interval_at, Instant};
+use tracing::Span;
 use uuid::Uuid;
 
 // type declaration
 type PgTransaction = Transaction<'static, Postgres>;
 
+// how long a chunk of claimed tasks is kept from the other workers, should the worker sending it stop
+// before settling every task, the rest are picked up again once it has elapsed
+pub(crate) const CLAIM_DURATION: Duration = Duration::from_secs(5 * 60);
+
+// how often the claim is renewed while the chunk is being sent, well within the claim duration so that a
+// send held up by the rate limit or a slow transport never loses its claim
+const CLAIM_RENEWAL_INTERVAL: Duration = Duration::from_secs(60);
+
 // a struct to represent a newsletter issue type
-struct NewsletterIssue {
+pub struct NewsletterIssue {
     title: String,
     text_content: String,
     html_content: String,
 }
 
+// a struct to represent a task dequeued from the issue delivery queue
+struct DeliveryTask {
+    newsletter_issue_id: Uuid,
+    subscriber_email: String,
+    n_attempts: i32,
+}
+
+// a struct to represent a subscriber an issue is still to be delivered to
+struct ConfirmedSubscriber {
+    id: Uuid,
+    name: String,
+}
+
+// an enum to represent the final outcome of delivering an issue to a single subscriber
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    Sent,
+    Failed,
+    SkippedInvalidEmail,
+    SkippedUnsubscribed,
+}
+
+// implementation to return the string stored in the newsletter_issue_deliveries log for each status
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Sent => "sent",
+            DeliveryStatus::Failed => "failed",
+            DeliveryStatus::SkippedInvalidEmail => "skipped_invalid_email",
+            DeliveryStatus::SkippedUnsubscribed => "skipped_unsubscribed",
+        }
+    }
+}
+
 // an enum to provide variants of the outcome of execution of try_execute_task
 pub enum ExecutionOutcome {
     TaskCompleted,
     EmptyQueue,
 }
 
-// the worker loop function
-async fn worker_loop(pool: PgPool, email_client: EmailClient) -> Result<(), anyhow::Error> {
-    loop {
-        match try_execute_task(&pool, &email_client).await {
-            Ok(ExecutionOutcome::EmptyQueue) => {
-                tokio::time::sleep(Duration::from_secs(10)).await;
-            }
-            Err(_) => {
-                tokio::time::sleep(Duration::from_secs(1)).await;
-            }
-            Ok(ExecutionOutcome::TaskCompleted) => {}
-        }
-    }
+// a struct to hold what the delivery workers share, built once so that every worker uses the same
+// connection pool and draws from the same email rate limit
+#[derive(Clone)]
+pub struct DeliveryWorkers {
+    pool: PgPool,
+    email_client: EmailClient,
+    settings: IssueDeliverySettings,
+    base_url: ApplicationBaseUrl,
+    hmac_secret: HmacSecret,
 }
 
-// function which runs the worker loop until it is stopped
-pub async fn run_delivery_until_stopped(configuration: Settings) -> Result<(), anyhow::Error> {
-    let connection_pool = get_connection_pool(&configuration.database);
-    let email_client = configuration.email_client.client();
-    worker_loop(connection_pool, email_client).await
+impl DeliveryWorkers {
+    pub fn build(
+        configuration: Settings,
+        email_client: EmailClient,
+    ) -> Result<Self, anyhow::Error> {
+        anyhow::ensure!(
+            configuration.issue_delivery.batch_size > 0,
+            "The issue delivery batch size must be at least 1."
+        );
+        Ok(Self {
+            pool: get_connection_pool(&configuration.database),
+            email_client,
+            settings: configuration.issue_delivery,
+            base_url: ApplicationBaseUrl(configuration.application.base_url),
+            hmac_secret: HmacSecret(configuration.application.hmac_secret.into()),
+        })
+    }
+
+    // the number of workers to spawn
+    pub fn n_workers(&self) -> u32 {
+        self.settings.n_workers
+    }
+
+    // function which runs a single worker loop until shutdown is requested
+    // each worker claims its own tasks, a claimed task isn't due again until its claim has elapsed, so the
+    // other workers leave it alone for as long as the worker sending it keeps renewing the claim
+    pub async fn run_until_stopped(
+        self,
+        worker_id: u32,
+        shutdown: Shutdown,
+    ) -> Result<(), anyhow::Error> {
+        self.worker_loop(worker_id, shutdown).await
+    }
+
+    // the worker loop function, a batch which is being sent when shutdown is requested is finished
+    // and settled before the worker stops dequeuing
+    #[tracing::instrument(name = "Email delivery worker", skip(self, shutdown))]
+    async fn worker_loop(
+        &self,
+        worker_id: u32,
+        mut shutdown: Shutdown,
+    ) -> Result<(), anyhow::Error> {
+        while !shutdown.is_requested() {
+            let wait_time = match try_execute_task(
+                &self.pool,
+                &self.email_client,
+                &self.settings,
+                &self.base_url,
+                &self.hmac_secret,
+            )
+            .await
+            {
+                Ok(ExecutionOutcome::EmptyQueue) => Duration::from_secs(10),
+                Err(_) => Duration::from_secs(1),
+                Ok(ExecutionOutcome::TaskCompleted) => continue,
+            };
+            shutdown.sleep(wait_time).await;
+        }
+        tracing::info!("Stopped dequeuing delivery tasks");
+        Ok(())
+    }
 }
 
-// enqueue tasks function
+// execute tasks function, delivers a chunk of queued tasks in a single batch
 #[tracing::instrument(
-  skip_all,
-  fields(
-    newsletter_issue_id=tracing::field::Empty,
-    subscriber_email=tracing::field::Empty
-  ),
-  err
+    skip_all,
+    fields(
+        n_tasks = tracing::field::Empty,
+        throttled_milliseconds = tracing::field::Empty
+    ),
+    err
 )]
 pub async fn try_execute_task(
     pool: &PgPool,
     email_client: &EmailClient,
+    settings: &IssueDeliverySettings,
+    base_url: &ApplicationBaseUrl,
+    hmac_secret: &HmacSecret,
 ) -> Result<ExecutionOutcome, anyhow::Error> {
-    let task = dequeue_task(pool).await?;
-    if task.is_none() {
+    let tasks = claim_tasks(pool, settings.batch_size).await?;
+    if tasks.is_empty() {
         return Ok(ExecutionOutcome::EmptyQueue);
     }
-    let (transaction, issue_id, email) = task.unwrap();
-    Span::current()
-        .record("newsletter_issue_id", display(issue_id))
-        .record("subscriber_email", display(&email));
-    match SubscriberEmail::parse(email.clone()) {
-        Ok(email) => {
-            let issue = get_issue(pool, issue_id).await?;
-            if let Err(e) = email_client
-                .send_email(
-                    &email,
-                    &issue.title,
-                    &issue.html_content,
-                    &issue.text_content,
-                )
-                .await
-            {
+    Span::current().record("n_tasks", tasks.len());
+
+    // the claim is released if the chunk can't be prepared, rather than left to elapse
+    let PreparedChunk {
+        prepared,
+        issues,
+        mut settled,
+    } = match prepare_tasks(pool, &tasks, settings, base_url, hmac_secret).await {
+        Ok(chunk) => chunk,
+        Err(e) => {
+            release_tasks(pool, &tasks).await;
+            return Err(e);
+        }
+    };
+    let headers: Vec<_> = prepared
+        .iter()
+        .map(|prepared| prepared.rendered.headers())
+        .collect();
+    let emails: Vec<_> = prepared
+        .iter()
+        .zip(&headers)
+        .map(|(prepared, headers)| {
+            let issue = &issues[&prepared.task.newsletter_issue_id];
+            prepared.rendered.email(&prepared.email, issue, headers)
+        })
+        .collect();
+
+    // map the per-message results back to their queue rows, each one settled on its own so that failing
+    // to record one delivery doesn't send the rest of the chunk again
+    let claimed: Vec<_> = prepared.iter().map(|prepared| prepared.task).collect();
+    let results = while_claimed(email_client.send_email_batch(&emails), || {
+        renew_claim(pool, &claimed)
+    })
+    .await;
+    for (task, result) in claimed.into_iter().zip(results) {
+        let outcome = match result {
+            Ok(()) => TaskOutcome::Sent,
+            Err(e) => TaskOutcome::Failed(e),
+        };
+        settled = settled.and(settle_task(pool, task, outcome, settings).await);
+    }
+    settled?;
+    Ok(ExecutionOutcome::TaskCompleted)
+}
+
+// a struct to represent a claimed task rendered for its recipient, ready to be sent
+struct PreparedTask<'a> {
+    task: &'a DeliveryTask,
+    email: SubscriberEmail,
+    rendered: RenderedIssue,
+}
+
+// a struct to represent a claimed chunk once the tasks which can't be delivered have been settled
+struct PreparedChunk<'a> {
+    prepared: Vec<PreparedTask<'a>>,
+    issues: HashMap<Uuid, NewsletterIssue>,
+    settled: Result<(), anyhow::Error>,
+}
+
+// function to settle the claimed tasks which can't be delivered and render every remaining task for its
+// recipient, expanding the placeholders of the issue; a chunk can span more than one issue
+async fn prepare_tasks<'a>(
+    pool: &PgPool,
+    tasks: &'a [DeliveryTask],
+    settings: &IssueDeliverySettings,
+    base_url: &ApplicationBaseUrl,
+    hmac_secret: &HmacSecret,
+) -> Result<PreparedChunk<'a>, anyhow::Error> {
+    let mut deliverable = Vec::with_capacity(tasks.len());
+    let mut settled = Ok(());
+    for task in tasks {
+        match SubscriberEmail::parse(task.subscriber_email.clone()) {
+            Ok(email) => match get_confirmed_subscriber(pool, email.as_ref()).await? {
+                Some(subscriber) => deliverable.push((task, email, subscriber)),
+                None => {
+                    tracing::warn!(
+                        newsletter_issue_id = %task.newsletter_issue_id,
+                        subscriber_email = %task.subscriber_email,
+                        "Skipping a subscriber who is no longer confirmed or is suppressed."
+                    );
+                    let outcome = TaskOutcome::Skipped(DeliveryStatus::SkippedUnsubscribed, None);
+                    settled = settled.and(settle_task(pool, task, outcome, settings).await);
+                }
+            },
+            Err(e) => {
                 tracing::error!(
+                    newsletter_issue_id = %task.newsletter_issue_id,
+                    subscriber_email = %task.subscriber_email,
                     error.cause_chain = ?e,
                     error.message = %e,
-                    "Failed to deliver issue to a confirmed subscriber. \
-                        Skipping.",
+                    "Skipping a confirmed subscriber. \
+                        Their stored contact details are invalid",
                 );
+                let outcome = TaskOutcome::Skipped(DeliveryStatus::SkippedInvalidEmail, Some(e));
+                settled = settled.and(settle_task(pool, task, outcome, settings).await);
             }
         }
-        Err(e) => {
-            tracing::error!(
-                error.cause_chain = ?e,
-                error.message = %e,
-                "Skipping a confirmed subscriber. \
-                    Their stored contact details are invalid",
-            );
+    }
+
+    let mut issues = HashMap::new();
+    for (task, _, _) in &deliverable {
+        if let Entry::Vacant(entry) = issues.entry(task.newsletter_issue_id) {
+            entry.insert(get_issue(pool, task.newsletter_issue_id).await?);
         }
     }
-    delete_task(transaction, issue_id, &email).await?;
-    Ok(ExecutionOutcome::TaskCompleted)
+    let prepared = deliverable
+        .into_iter()
+        .map(|(task, email, subscriber)| {
+            let unsubscribe_link = unsubscribe_link(base_url, hmac_secret, subscriber.id);
+            let personalization = Personalization {
+                name: &subscriber.name,
+                email: email.as_ref(),
+                unsubscribe_url: &unsubscribe_link,
+            };
+            let rendered =
+                RenderedIssue::new(&issues[&task.newsletter_issue_id], &personalization)?;
+            Ok(PreparedTask {
+                task,
+                email,
+                rendered,
+            })
+        })
+        .collect::<Result<_, askama::Error>>()?;
+    Ok(PreparedChunk {
+        prepared,
+        issues,
+        settled,
+    })
 }
 
-// function to dequeue tasks
-#[tracing::instrument(skip_all)]
-async fn dequeue_task(
+// function to drive a future to completion while renewing a claim at a regular interval, shared with the email
+// outbox worker
+pub(crate) async fn while_claimed<F, R, RF>(future: F, mut renew: R) -> F::Output
+where
+    F: Future,
+    R: FnMut() -> RF,
+    RF: Future<Output = Result<(), anyhow::Error>>,
+{
+    tokio::pin!(future);
+    let mut renewal = interval_at(
+        Instant::now() + CLAIM_RENEWAL_INTERVAL,
+        CLAIM_RENEWAL_INTERVAL,
+    );
+    loop {
+        tokio::select! {
+            output = &mut future => return output,
+            _ = renewal.tick() => {
+                if let Err(e) = renew().await {
+                    tracing::error!(
+                        error.cause_chain = ?e,
+                        error.message = %e,
+                        "Failed to renew a claim.",
+                    );
+                }
+            }
+        }
+    }
+}
+
+// an enum to represent what happened to a claimed task
+enum TaskOutcome {
+    Sent,
+    Failed(anyhow::Error),
+    Skipped(DeliveryStatus, Option<String>),
+}
+
+// function to record the outcome of a single task in its own transaction; an error is logged, the task
+// is then left to be picked up again once its claim has elapsed
+async fn settle_task(
     pool: &PgPool,
-) -> Result<Option<(PgTransaction, Uuid, String)>, anyhow::Error> {
-    let mut transaction = pool.begin().await?;
-    let r = sqlx::query!(
+    task: &DeliveryTask,
+    outcome: TaskOutcome,
+    settings: &IssueDeliverySettings,
+) -> Result<(), anyhow::Error> {
+    let settled = async {
+        let mut transaction = pool.begin().await?;
+        match outcome {
+            TaskOutcome::Sent => {
+                record_delivery(&mut transaction, task, DeliveryStatus::Sent, None).await?;
+                delete_task(&mut transaction, task).await?;
+            }
+            TaskOutcome::Failed(e) => {
+                handle_failed_delivery(&mut transaction, task, &e, settings).await?
+            }
+            TaskOutcome::Skipped(status, error) => {
+                record_delivery(&mut transaction, task, status, error.as_deref()).await?;
+                delete_task(&mut transaction, task).await?;
+            }
+        }
+        transaction.commit().await?;
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+    if let Err(e) = &settled {
+        tracing::error!(
+            newsletter_issue_id = %task.newsletter_issue_id,
+            subscriber_email = %task.subscriber_email,
+            error.cause_chain = ?e,
+            error.message = %e,
+            "Failed to record the outcome of a delivery task.",
+        );
+    }
+    settled
+}
+
+// function to either reschedule a failed delivery or, once out of retries, move it to the dead-letter table
+async fn handle_failed_delivery(
+    transaction: &mut PgTransaction,
+    task: &DeliveryTask,
+    e: &anyhow::Error,
+    settings: &IssueDeliverySettings,
+) -> Result<(), anyhow::Error> {
+    let n_attempts = task.n_attempts + 1;
+    if n_attempts as u32 > settings.max_retries {
+        tracing::error!(
+            newsletter_issue_id = %task.newsletter_issue_id,
+            subscriber_email = %task.subscriber_email,
+            error.cause_chain = ?e,
+            error.message = %e,
+            n_attempts,
+            "Failed to deliver issue to a confirmed subscriber. \
+                Giving up and moving the task to the dead-letter table.",
+        );
+        let error = e.to_string();
+        record_delivery(transaction, task, DeliveryStatus::Failed, Some(&error)).await?;
+        fail_task(transaction, task, n_attempts, &error).await
+    } else {
+        let delay = retry_delay(n_attempts as u32, settings);
+        tracing::warn!(
+            newsletter_issue_id = %task.newsletter_issue_id,
+            subscriber_email = %task.subscriber_email,
+            error.cause_chain = ?e,
+            error.message = %e,
+            n_attempts,
+            retry_in_milliseconds = delay.as_millis() as u64,
+            "Failed to deliver issue to a confirmed subscriber. \
+                Scheduling a retry.",
+        );
+        reschedule_task(transaction, task, n_attempts, delay).await
+    }
+}
+
+// function to build the personalised one-click unsubscribe link of a subscriber
+pub fn unsubscribe_link(
+    base_url: &ApplicationBaseUrl,
+    hmac_secret: &HmacSecret,
+    subscriber_id: Uuid,
+) -> String {
+    let unsubscribe_token = UnsubscribeToken::generate(subscriber_id, &hmac_secret.0);
+    format!(
+        "{}/subscriptions/unsubscribe?token={}",
+        base_url.0,
+        unsubscribe_token.as_ref()
+    )
+}
+
+// a struct to represent an issue rendered for a single recipient
+struct RenderedIssue {
+    html_content: String,
+    text_content: String,
+    list_unsubscribe: String,
+}
+
+impl RenderedIssue {
+    // expands the placeholders of the html and plain text bodies of the issue for the recipient, and wraps
+    // them in the email header and the footer carrying the personalised unsubscribe link
+    fn new(
+        issue: &NewsletterIssue,
+        personalization: &Personalization<'_>,
+    ) -> Result<Self, askama::Error> {
+        let unsubscribe_link = personalization.unsubscribe_url;
+        let body = EmailBody::render(
+            IssueEmailHtml {
+                content: &personalization.expand_html(&issue.html_content),
+                unsubscribe_link,
+            },
+            IssueEmailText {
+                content: &personalization.expand_text(&issue.text_content),
+                unsubscribe_link,
+            },
+        )?;
+        Ok(Self {
+            html_content: body.html,
+            text_content: body.text,
+            list_unsubscribe: format!("<{}>", unsubscribe_link),
+        })
+    }
+
+    fn headers(&self) -> [EmailHeader<'_>; 2] {
+        [
+            EmailHeader {
+                name: "List-Unsubscribe",
+                value: &self.list_unsubscribe,
+            },
+            EmailHeader {
+                name: "List-Unsubscribe-Post",
+                value: "List-Unsubscribe=One-Click",
+            },
+        ]
+    }
+
+    fn email<'a>(
+        &'a self,
+        recipient: &'a SubscriberEmail,
+        issue: &'a NewsletterIssue,
+        headers: &'a [EmailHeader<'a>],
+    ) -> Email<'a> {
+        Email {
+            recipient,
+            subject: &issue.title,
+            html_content: &self.html_content,
+            text_content: &self.text_content,
+            headers,
+        }
+    }
+}
+
+// function to render an issue for a single recipient and send it, along with the list unsubscribe headers
+pub async fn send_issue(
+    email_client: &EmailClient,
+    recipient: &SubscriberEmail,
+    issue: &NewsletterIssue,
+    personalization: &Personalization<'_>,
+) -> Result<(), anyhow::Error> {
+    let rendered = RenderedIssue::new(issue, personalization)?;
+    let headers = rendered.headers();
+    email_client
+        .send_email_with_headers(
+            recipient,
+            &issue.title,
+            &rendered.html_content,
+            &rendered.text_content,
+            &headers,
+        )
+        .await
+}
+
+// a function to queue delivery tasks
+#[tracing::instrument(skip_all)]
+pub async fn enqueue_delivery_tasks(
+    transaction: &mut Transaction<'_, Postgres>,
+    newsletter_issue_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO issue_delivery_queue (
+            newsletter_issue_id,
+            subscriber_email
+        )
+        SELECT $1, email
+        FROM subscriptions
+        WHERE
+            status = 'confirmed' AND
+            NOT EXISTS (
+                SELECT 1 FROM suppressions WHERE suppressions.email = lower(subscriptions.email)
+            )
+        "#,
+        newsletter_issue_id,
+    );
+    transaction.execute(query).await?;
+    Ok(())
+}
+
+// function to compute how long to wait before the next delivery attempt
+fn retry_delay(n_attempts: u32, settings: &IssueDeliverySettings) -> Duration {
+    backoff_delay(
+        n_attempts,
+        settings.retry_base_delay(),
+        settings.retry_max_delay(),
+    )
+}
+
+// function to compute an exponential backoff plus jitter, shared with the email outbox worker
+pub(crate) fn backoff_delay(
+    n_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+) -> Duration {
+    let exponential_delay = base_delay
+        .checked_mul(2u32.saturating_pow(n_attempts.saturating_sub(1)))
+        .unwrap_or(Duration::MAX)
+        .min(max_delay);
+    let jitter_millis = (base_delay.as_millis() / 2) as u64;
+    let jitter = Duration::from_millis(rand::thread_rng().gen_range(0..=jitter_millis));
+    exponential_delay + jitter
+}
+
+// function to claim a chunk of due tasks, their next attempt is pushed back by the claim duration so that
+// no other worker picks them up while they are being sent; the rows are only locked while claiming them
+#[tracing::instrument(skip_all)]
+async fn claim_tasks(pool: &PgPool, batch_size: u32) -> Result<Vec<DeliveryTask>, anyhow::Error> {
+    let tasks = sqlx::query_as!(
+        DeliveryTask,
         r#"
-    SELECT newsletter_issue_id, subscriber_email
-    FROM issue_delivery_queue
-    FOR UPDATE
-    SKIP LOCKED
-    LIMIT 1
+    UPDATE issue_delivery_queue
+    SET next_attempt_at = now() + make_interval(secs => $2)
+    WHERE (newsletter_issue_id, subscriber_email) IN (
+        SELECT newsletter_issue_id, subscriber_email
+        FROM issue_delivery_queue
+        WHERE next_attempt_at <= now()
+        FOR UPDATE
+        SKIP LOCKED
+        LIMIT $1
+    )
+    RETURNING newsletter_issue_id, subscriber_email, n_attempts
     "#,
+        i64::from(batch_size),
+        CLAIM_DURATION.as_secs_f64()
     )
-    .fetch_optional(&mut *transaction)
+    .fetch_all(pool)
     .await?;
-    if let Some(r) = r {
-        Ok(Some((
-            transaction,
-            r.newsletter_issue_id,
-            r.subscriber_email,
-        )))
-    } else {
-        Ok(None)
+    Ok(tasks)
+}
+
+// function to push the claim on the given tasks back by another claim duration
+#[tracing::instrument(skip_all)]
+async fn renew_claim(pool: &PgPool, tasks: &[&DeliveryTask]) -> Result<(), anyhow::Error> {
+    let (issue_ids, subscriber_emails) = task_keys(tasks.iter().copied());
+    sqlx::query!(
+        r#"
+UPDATE issue_delivery_queue
+SET next_attempt_at = now() + make_interval(secs => $3)
+WHERE (newsletter_issue_id, subscriber_email) IN (
+    SELECT * FROM UNNEST($1::uuid[], $2::text[])
+)
+"#,
+        &issue_ids,
+        &subscriber_emails,
+        CLAIM_DURATION.as_secs_f64()
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// function to release the claim on the given tasks, making those still queued due again straight away; an
+// error is only logged, the claim then elapses on its own
+#[tracing::instrument(skip_all)]
+async fn release_tasks(pool: &PgPool, tasks: &[DeliveryTask]) {
+    let (issue_ids, subscriber_emails) = task_keys(tasks);
+    let released = sqlx::query!(
+        r#"
+UPDATE issue_delivery_queue
+SET next_attempt_at = now()
+WHERE (newsletter_issue_id, subscriber_email) IN (
+    SELECT * FROM UNNEST($1::uuid[], $2::text[])
+)
+"#,
+        &issue_ids,
+        &subscriber_emails,
+    )
+    .execute(pool)
+    .await;
+    if let Err(e) = released {
+        tracing::error!(
+            error.cause_chain = ?e,
+            error.message = %e,
+            "Failed to release the claim on a chunk of delivery tasks.",
+        );
     }
 }
 
+// function to split the keys of the given tasks into the arrays bound by the claim queries
+fn task_keys<'a>(tasks: impl IntoIterator<Item = &'a DeliveryTask>) -> (Vec<Uuid>, Vec<String>) {
+    tasks
+        .into_iter()
+        .map(|task| (task.newsletter_issue_id, task.subscriber_email.clone()))
+        .unzip()
+}
+
+// function to push a failed task back into the queue, to be picked up again once the delay has elapsed
+#[tracing::instrument(skip_all)]
+async fn reschedule_task(
+    transaction: &mut PgTransaction,
+    task: &DeliveryTask,
+    n_attempts: i32,
+    delay: Duration,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+UPDATE issue_delivery_queue
+SET
+n_attempts = $3,
+next_attempt_at = now() + make_interval(secs => $4)
+WHERE
+newsletter_issue_id = $1 AND
+subscriber_email = $2
+"#,
+        task.newsletter_issue_id,
+        task.subscriber_email,
+        n_attempts,
+        delay.as_secs_f64()
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+// function to record the outcome of a delivery in the per-issue delivery log
+#[tracing::instrument(skip_all)]
+async fn record_delivery(
+    transaction: &mut PgTransaction,
+    task: &DeliveryTask,
+    status: DeliveryStatus,
+    error: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+INSERT INTO newsletter_issue_deliveries (
+newsletter_issue_id,
+subscriber_email,
+status,
+error,
+recorded_at
+)
+VALUES ($1, $2, $3, $4, now())
+ON CONFLICT (newsletter_issue_id, subscriber_email) DO UPDATE
+SET
+status = EXCLUDED.status,
+error = EXCLUDED.error,
+recorded_at = EXCLUDED.recorded_at
+"#,
+        task.newsletter_issue_id,
+        task.subscriber_email,
+        status.as_str(),
+        error
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+// function to move a task which has exhausted its retries into the dead-letter table
+#[tracing::instrument(skip_all)]
+async fn fail_task(
+    transaction: &mut PgTransaction,
+    task: &DeliveryTask,
+    n_attempts: i32,
+    last_error: &str,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+INSERT INTO issue_delivery_failures (
+newsletter_issue_id,
+subscriber_email,
+n_attempts,
+last_error,
+failed_at
+)
+VALUES ($1, $2, $3, $4, now())
+ON CONFLICT (newsletter_issue_id, subscriber_email) DO UPDATE
+SET
+n_attempts = EXCLUDED.n_attempts,
+last_error = EXCLUDED.last_error,
+failed_at = EXCLUDED.failed_at
+"#,
+        task.newsletter_issue_id,
+        task.subscriber_email,
+        n_attempts,
+        last_error
+    )
+    .execute(&mut **transaction)
+    .await?;
+    delete_task(transaction, task).await
+}
+
 // function to delete a task from the queue
 #[tracing::instrument(skip_all)]
 async fn delete_task(
-    mut transaction: PgTransaction,
-    issue_id: Uuid,
-    email: &str,
+    transaction: &mut PgTransaction,
+    task: &DeliveryTask,
 ) -> Result<(), anyhow::Error> {
     sqlx::query!(
         r#"
@@ -143,17 +749,39 @@ WHERE
 newsletter_issue_id = $1 AND
 subscriber_email = $2
 "#,
-        issue_id,
-        email
+        task.newsletter_issue_id,
+        task.subscriber_email
     )
-    .execute(&mut *transaction)
+    .execute(&mut **transaction)
     .await?;
-    transaction.commit().await?;
     Ok(())
 }
 
+// function to look up a subscriber, as long as they are still confirmed and not suppressed
 #[tracing::instrument(skip_all)]
-async fn get_issue(pool: &PgPool, issue_id: Uuid) -> Result<NewsletterIssue, anyhow::Error> {
+async fn get_confirmed_subscriber(
+    pool: &PgPool,
+    email: &str,
+) -> Result<Option<ConfirmedSubscriber>, anyhow::Error> {
+    let subscriber = sqlx::query_as!(
+        ConfirmedSubscriber,
+        r#"
+SELECT id, name
+FROM subscriptions
+WHERE
+email = $1 AND
+status = 'confirmed' AND
+NOT EXISTS (SELECT 1 FROM suppressions WHERE suppressions.email = lower($1))
+"#,
+        email
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(subscriber)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_issue(pool: &PgPool, issue_id: Uuid) -> Result<NewsletterIssue, anyhow::Error> {
     let issue = sqlx::query_as!(
         NewsletterIssue,
         r#"
@@ -168,3 +796,38 @@ newsletter_issue_id = $1
     .await?;
     Ok(issue)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::retry_delay;
+    use crate::configuration::IssueDeliverySettings;
+    use std::time::Duration;
+
+    fn settings() -> IssueDeliverySettings {
+        IssueDeliverySettings {
+            n_workers: 1,
+            batch_size: 100,
+            max_retries: 5,
+            retry_base_delay_milliseconds: 1000,
+            retry_max_delay_milliseconds: 10000,
+        }
+    }
+
+    #[test]
+    fn retry_delay_grows_exponentially_with_the_number_of_attempts() {
+        let settings = settings();
+        for (n_attempts, expected) in [(1, 1000), (2, 2000), (3, 4000), (4, 8000)] {
+            let delay = retry_delay(n_attempts, &settings);
+            assert!(delay >= Duration::from_millis(expected));
+            assert!(delay <= Duration::from_millis(expected + 500));
+        }
+    }
+
+    #[test]
+    fn retry_delay_is_capped_at_the_maximum_delay() {
+        let settings = settings();
+        let delay = retry_delay(30, &settings);
+        assert!(delay >= Duration::from_millis(10000));
+        assert!(delay <= Duration::from_millis(10500));
+    }
+}
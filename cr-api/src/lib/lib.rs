@@ -1,15 +1,21 @@
 // lib.rs
 
+pub mod audit_log;
 pub mod authentication;
 pub mod configuration;
 pub mod domain;
 pub mod email_client;
+pub mod email_outbox_worker;
 pub mod errors;
 pub mod idempotency;
 pub mod idempotency_cleanup_worker;
 pub mod issue_delivery_worker;
+pub mod newsletter_scheduling_worker;
+pub mod rate_limiter;
 pub mod routes;
 pub mod session_state;
+pub mod shutdown;
 pub mod startup;
 pub mod state;
+pub mod suppressions;
 pub mod telemetry;
@@ -0,0 +1,73 @@
+// src/lib/newsletter_scheduling_worker.rs
+
+// dependencies
+use crate::domain::IssueSlug;
+use crate::issue_delivery_worker::enqueue_delivery_tasks;
+use crate::shutdown::Shutdown;
+use crate::{configuration::Settings, startup::get_connection_pool};
+use sqlx::PgPool;
+use std::time::Duration;
+
+// function to run the newsletter scheduling worker until stopped
+pub async fn run_scheduling_until_stopped(
+    configuration: Settings,
+    shutdown: Shutdown,
+) -> Result<(), anyhow::Error> {
+    let connection_pool = get_connection_pool(&configuration.database);
+    worker_loop(connection_pool, shutdown).await
+}
+
+// function to run the newsletter scheduling worker in a loop, until shutdown is requested
+async fn worker_loop(pool: PgPool, mut shutdown: Shutdown) -> Result<(), anyhow::Error> {
+    while !shutdown.is_requested() {
+        if let Err(e) = publish_due_issues(&pool).await {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "Failed to publish scheduled newsletter issues.",
+            );
+        }
+        shutdown.sleep(Duration::from_secs(30)).await;
+    }
+    Ok(())
+}
+
+// function to promote scheduled issues which are due to published, give them the slug of their archive page
+// and enqueue their delivery tasks; all of it happens in a single transaction and only rows still marked as
+// scheduled are promoted, so each issue is enqueued exactly once even with several workers running
+#[tracing::instrument(skip_all)]
+pub async fn publish_due_issues(pool: &PgPool) -> Result<(), anyhow::Error> {
+    let mut transaction = pool.begin().await?;
+    let due_issues = sqlx::query!(
+        r#"
+        UPDATE newsletter_issues
+        SET
+            status = 'published',
+            published_at = now(),
+            updated_at = now()
+        WHERE
+            status = 'scheduled' AND
+            scheduled_for <= now()
+        RETURNING newsletter_issue_id, title
+        "#,
+    )
+    .fetch_all(&mut *transaction)
+    .await?;
+    for issue in due_issues {
+        tracing::info!(
+            newsletter_issue_id = %issue.newsletter_issue_id,
+            "Publishing a scheduled newsletter issue."
+        );
+        let slug = IssueSlug::new(&issue.title, issue.newsletter_issue_id);
+        sqlx::query!(
+            r#"UPDATE newsletter_issues SET slug = $2 WHERE newsletter_issue_id = $1"#,
+            issue.newsletter_issue_id,
+            slug.as_ref(),
+        )
+        .execute(&mut *transaction)
+        .await?;
+        enqueue_delivery_tasks(&mut transaction, issue.newsletter_issue_id).await?;
+    }
+    transaction.commit().await?;
+    Ok(())
+}
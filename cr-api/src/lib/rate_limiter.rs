@@ -0,0 +1,158 @@
+// src/lib/rate_limiter.rs
+
+// dependencies
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::Instrument;
+
+// a struct to represent a single token bucket, refilled continuously up to its capacity
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    // returns a full bucket allowing `capacity` messages per `period`
+    fn new(capacity: NonZeroU32, period: Duration, now: Instant) -> Self {
+        let capacity = f64::from(capacity.get());
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_second: capacity / period.as_secs_f64(),
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    // how long until a whole token is available, zero if there is one already
+    fn wait_time(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_second)
+        }
+    }
+}
+
+// a token bucket rate limiter for outgoing emails
+// every bucket has to hand out a token before a message may be sent, the limiter is meant to be shared behind an Arc
+#[derive(Debug)]
+pub struct RateLimiter {
+    buckets: Mutex<Vec<TokenBucket>>,
+}
+
+impl RateLimiter {
+    // returns a limiter for the given limits, None if there is nothing to limit
+    pub fn new(per_second: Option<NonZeroU32>, per_hour: Option<NonZeroU32>) -> Option<Self> {
+        let now = Instant::now();
+        let buckets: Vec<TokenBucket> = [
+            per_second.map(|limit| TokenBucket::new(limit, Duration::from_secs(1), now)),
+            per_hour.map(|limit| TokenBucket::new(limit, Duration::from_secs(3600), now)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if buckets.is_empty() {
+            None
+        } else {
+            Some(Self {
+                buckets: Mutex::new(buckets),
+            })
+        }
+    }
+
+    // takes a token from every bucket if they all have one, otherwise returns how long until they do
+    fn try_acquire(&self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self
+            .buckets
+            .lock()
+            .expect("Rate limiter lock was poisoned.");
+        let wait_time = buckets
+            .iter_mut()
+            .map(|bucket| {
+                bucket.refill(now);
+                bucket.wait_time()
+            })
+            .max()
+            .unwrap_or(Duration::ZERO);
+        if wait_time.is_zero() {
+            buckets.iter_mut().for_each(|bucket| bucket.tokens -= 1.0);
+            Ok(())
+        } else {
+            Err(wait_time)
+        }
+    }
+
+    // waits until a message may be sent and takes its token, returns how long the caller was throttled for
+    pub async fn acquire(&self) -> Duration {
+        let mut throttled = Duration::ZERO;
+        while let Err(wait_time) = self.try_acquire() {
+            tokio::time::sleep(wait_time)
+                .instrument(tracing::info_span!(
+                    "Throttled by the email rate limit",
+                    wait_milliseconds = wait_time.as_millis() as u64
+                ))
+                .await;
+            throttled += wait_time;
+        }
+        throttled
+    }
+}
+
+// unit tests for the rate limiter, time is paused so that waiting is instantaneous
+#[cfg(test)]
+mod tests {
+    use super::RateLimiter;
+    use std::num::NonZeroU32;
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[test]
+    fn no_limits_means_no_limiter() {
+        assert!(RateLimiter::new(None, None).is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_burst_up_to_the_per_second_limit_is_not_throttled() {
+        let limiter = RateLimiter::new(NonZeroU32::new(5), None).unwrap();
+        for _ in 0..5 {
+            assert_eq!(limiter.acquire().await, Duration::ZERO);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn messages_above_the_per_second_limit_are_throttled() {
+        let limiter = RateLimiter::new(NonZeroU32::new(10), None).unwrap();
+        let start = Instant::now();
+        for _ in 0..30 {
+            limiter.acquire().await;
+        }
+        // the first 10 go out straight away, the next 20 at 10 per second
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(1990), "{:?}", elapsed);
+        assert!(elapsed <= Duration::from_millis(2010), "{:?}", elapsed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn the_per_hour_limit_applies_on_top_of_the_per_second_limit() {
+        let limiter = RateLimiter::new(NonZeroU32::new(10), NonZeroU32::new(2)).unwrap();
+        limiter.acquire().await;
+        limiter.acquire().await;
+        let throttled = limiter.acquire().await;
+        assert!(throttled >= Duration::from_secs(1799), "{:?}", throttled);
+        assert!(throttled <= Duration::from_secs(1801), "{:?}", throttled);
+    }
+}
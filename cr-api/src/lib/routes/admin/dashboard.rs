@@ -30,6 +30,23 @@ pub async fn get_username(user_id: Uuid, pool: &PgPool) -> Result<String, anyhow
     Ok(row.username)
 }
 
+// function which retrieves the email address test emails are sent to, if the user has set one
+#[tracing::instrument(name = "Get user email", skip(pool))]
+pub async fn get_user_email(user_id: Uuid, pool: &PgPool) -> Result<Option<String>, anyhow::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT email
+        FROM users
+        WHERE user_id = $1
+        "#,
+        user_id,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to perform a query to retrieve the user email.")?;
+    Ok(row.email)
+}
+
 // handler to render the admin dashboard, displaying the current logged in user's name
 #[debug_handler]
 pub async fn admin_dashboard(
@@ -0,0 +1,174 @@
+// src/routes/admin/drafts/get.rs
+
+// dependencies
+use crate::domain::{DraftFormTemplate, DraftPreviewTemplate, DraftSummary, DraftsTemplate};
+use crate::errors::{e404, e500, ResponseError};
+use crate::state::AppState;
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum_flash::IncomingFlashes;
+use sqlx::PgPool;
+use std::fmt::Write;
+use uuid::Uuid;
+
+// a struct to represent a stored draft
+struct Draft {
+    title: String,
+    text_content: String,
+    html_content: String,
+}
+
+// function which retrieves all the drafts
+#[tracing::instrument(name = "Get drafts", skip(pool))]
+async fn get_drafts(pool: &PgPool) -> Result<Vec<DraftSummary>, anyhow::Error> {
+    let drafts = sqlx::query!(
+        r#"
+        SELECT newsletter_issue_id, title
+        FROM newsletter_issues
+        WHERE status = 'draft'
+        ORDER BY title
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to perform a query to retrieve the drafts.")?
+    .into_iter()
+    .map(|r| DraftSummary {
+        newsletter_issue_id: r.newsletter_issue_id,
+        title: r.title,
+        idempotency_key: Uuid::new_v4(),
+    })
+    .collect();
+    Ok(drafts)
+}
+
+// function which retrieves a single draft
+#[tracing::instrument(name = "Get draft", skip(pool))]
+async fn get_draft(
+    pool: &PgPool,
+    newsletter_issue_id: Uuid,
+) -> Result<Option<Draft>, anyhow::Error> {
+    let draft = sqlx::query_as!(
+        Draft,
+        r#"
+        SELECT title, text_content, html_content
+        FROM newsletter_issues
+        WHERE
+            newsletter_issue_id = $1 AND
+            status = 'draft'
+        "#,
+        newsletter_issue_id
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to perform a query to retrieve the draft.")?;
+    Ok(draft)
+}
+
+// function which retrieves a single draft, turning a missing draft into a 404
+async fn get_existing_draft(
+    pool: &PgPool,
+    newsletter_issue_id: Uuid,
+) -> Result<Draft, ResponseError> {
+    get_draft(pool, newsletter_issue_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| anyhow::anyhow!("There is no draft with the provided id."))
+        .map_err(e404)
+}
+
+// handler to render the list of drafts
+#[tracing::instrument(name = "Drafts", skip(flashes, app_state))]
+pub async fn drafts(
+    flashes: IncomingFlashes,
+    State(app_state): State<AppState>,
+) -> Result<(IncomingFlashes, DraftsTemplate), ResponseError> {
+    // process any incoming flash messages
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    let drafts = get_drafts(&app_state.db_pool).await.map_err(e500)?;
+
+    // render the list of drafts from its associated Askama template
+    let drafts_template = DraftsTemplate { flash_msg, drafts };
+
+    Ok((flashes, drafts_template))
+}
+
+// handler to render an empty form to start a new draft
+#[tracing::instrument(name = "New draft form", skip(flashes))]
+pub async fn new_draft_form(flashes: IncomingFlashes) -> (IncomingFlashes, DraftFormTemplate) {
+    // process any incoming flash messages
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    // render the draft form from its associated Askama template
+    let draft_form_template = DraftFormTemplate {
+        flash_msg,
+        newsletter_issue_id: None,
+        form_action: "/admin/drafts".to_string(),
+        title: String::new(),
+        text_content: String::new(),
+        html_content: String::new(),
+    };
+
+    (flashes, draft_form_template)
+}
+
+// handler to render the form to edit an existing draft
+#[tracing::instrument(name = "Edit draft form", skip(flashes, app_state))]
+pub async fn edit_draft_form(
+    flashes: IncomingFlashes,
+    State(app_state): State<AppState>,
+    Path(newsletter_issue_id): Path<Uuid>,
+) -> Result<(IncomingFlashes, DraftFormTemplate), ResponseError> {
+    // process any incoming flash messages
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    let draft = get_existing_draft(&app_state.db_pool, newsletter_issue_id).await?;
+
+    // render the draft form, filled in with the stored draft, from its associated Askama template
+    let draft_form_template = DraftFormTemplate {
+        flash_msg,
+        newsletter_issue_id: Some(newsletter_issue_id),
+        form_action: format!("/admin/drafts/{}", newsletter_issue_id),
+        title: draft.title,
+        text_content: draft.text_content,
+        html_content: draft.html_content,
+    };
+
+    Ok((flashes, draft_form_template))
+}
+
+// handler to render the html body of a draft inside the site layout, exactly as it will be sent
+#[tracing::instrument(name = "Preview draft", skip(flashes, app_state))]
+pub async fn preview_draft(
+    flashes: IncomingFlashes,
+    State(app_state): State<AppState>,
+    Path(newsletter_issue_id): Path<Uuid>,
+) -> Result<(IncomingFlashes, DraftPreviewTemplate), ResponseError> {
+    // process any incoming flash messages
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    let draft = get_existing_draft(&app_state.db_pool, newsletter_issue_id).await?;
+
+    // render the draft preview from its associated Askama template
+    let draft_preview_template = DraftPreviewTemplate {
+        flash_msg,
+        newsletter_issue_id,
+        title: draft.title,
+        html_content: draft.html_content,
+    };
+
+    Ok((flashes, draft_preview_template))
+}
@@ -0,0 +1,7 @@
+// src/lib/routes/admin/drafts/mod.rs
+
+mod get;
+mod post;
+
+pub use get::{drafts, edit_draft_form, new_draft_form, preview_draft};
+pub use post::{create_draft, edit_draft, publish_draft, remove_draft};
@@ -0,0 +1,332 @@
+// src/routes/admin/drafts/post.rs
+
+// dependencies
+use crate::authentication::UserId;
+use crate::domain::{validate_placeholders, IssueSlug};
+use crate::errors::{e400, e404, e500, ResponseError};
+use crate::idempotency::IdempotencyKey;
+use crate::idempotency::{save_response, try_processing, NextAction};
+use crate::issue_delivery_worker::enqueue_delivery_tasks;
+use crate::routes::admin::newsletter::post::PUBLISH_SUCCESS_INFO_MESSAGE;
+use crate::state::AppState;
+use anyhow::Context;
+use axum::{
+    extract::{Form, Path, State},
+    response::{IntoResponse, Redirect},
+    Extension,
+};
+use axum_flash::Flash;
+use serde::Deserialize;
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+use validator::Validate;
+
+// a struct to represent the form data received from the draft form, drafts may be incomplete
+#[derive(Clone, Debug, Deserialize, Validate)]
+pub struct DraftData {
+    #[validate(length(min = 1))]
+    title: String,
+    text_content: String,
+    html_content: String,
+}
+
+// a struct to represent the form data received from the publish draft form
+#[derive(Debug, Deserialize)]
+pub struct PublishDraftData {
+    idempotency_key: String,
+}
+
+// a struct to represent the content of a draft that is about to be published
+#[derive(Debug, Validate)]
+struct DraftContent {
+    #[validate(length(min = 5))]
+    title: String,
+    #[validate(length(min = 5))]
+    text_content: String,
+    #[validate(length(min = 5))]
+    html_content: String,
+}
+
+// impl block for the draft content type
+impl DraftContent {
+    // drafts may use any placeholder while being written, only the known ones can be published
+    fn validate_placeholders(&self) -> Result<(), String> {
+        validate_placeholders(&self.html_content, &self.text_content)
+    }
+}
+
+pub static DRAFT_SAVED_INFO_MESSAGE: &str = "The draft has been saved.";
+
+pub static DRAFT_DELETED_INFO_MESSAGE: &str = "The draft has been deleted.";
+
+// a function which stores a new draft
+#[tracing::instrument(skip_all)]
+async fn insert_draft(pool: &PgPool, draft: &DraftData) -> Result<Uuid, sqlx::Error> {
+    let newsletter_issue_id = Uuid::new_v4();
+    sqlx::query!(
+        r#"
+        INSERT INTO newsletter_issues (
+            newsletter_issue_id,
+            title,
+            text_content,
+            html_content,
+            status
+        )
+        VALUES ($1, $2, $3, $4, 'draft')
+        "#,
+        newsletter_issue_id,
+        draft.title,
+        draft.text_content,
+        draft.html_content,
+    )
+    .execute(pool)
+    .await?;
+    Ok(newsletter_issue_id)
+}
+
+// a function which updates an existing draft, returns false if there is no such draft
+#[tracing::instrument(skip(pool, draft))]
+async fn update_draft(
+    pool: &PgPool,
+    newsletter_issue_id: Uuid,
+    draft: &DraftData,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE newsletter_issues
+        SET
+            title = $2,
+            text_content = $3,
+            html_content = $4,
+            updated_at = now()
+        WHERE
+            newsletter_issue_id = $1 AND
+            status = 'draft'
+        "#,
+        newsletter_issue_id,
+        draft.title,
+        draft.text_content,
+        draft.html_content,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// a function which deletes a draft, returns false if there is no such draft
+#[tracing::instrument(skip(pool))]
+async fn delete_draft(pool: &PgPool, newsletter_issue_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM newsletter_issues
+        WHERE
+            newsletter_issue_id = $1 AND
+            status = 'draft'
+        "#,
+        newsletter_issue_id,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// a function which locks a draft for the rest of the transaction and returns its content
+#[tracing::instrument(skip(transaction))]
+async fn get_draft_for_update(
+    transaction: &mut Transaction<'_, Postgres>,
+    newsletter_issue_id: Uuid,
+) -> Result<Option<DraftContent>, sqlx::Error> {
+    sqlx::query_as!(
+        DraftContent,
+        r#"
+        SELECT title, text_content, html_content
+        FROM newsletter_issues
+        WHERE
+            newsletter_issue_id = $1 AND
+            status = 'draft'
+        FOR UPDATE
+        "#,
+        newsletter_issue_id,
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+}
+
+// a function which turns a draft into a published newsletter issue, with the slug of its archive page
+#[tracing::instrument(skip(transaction, slug))]
+async fn mark_draft_as_published(
+    transaction: &mut Transaction<'_, Postgres>,
+    newsletter_issue_id: Uuid,
+    slug: &IssueSlug,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        UPDATE newsletter_issues
+        SET
+            status = 'published',
+            published_at = now(),
+            updated_at = now(),
+            slug = $2
+        WHERE newsletter_issue_id = $1
+        "#,
+        newsletter_issue_id,
+        slug.as_ref(),
+    );
+    transaction.execute(query).await?;
+    Ok(())
+}
+
+// create draft handler
+#[tracing::instrument(
+name = "Create a draft",
+skip(flash, draft_data, app_state, user_id),
+fields(user_id=%*user_id)
+)]
+pub async fn create_draft(
+    Extension(user_id): Extension<UserId>,
+    flash: Flash,
+    State(app_state): State<AppState>,
+    draft_data: Form<DraftData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    if draft_data.validate().is_err() {
+        let flash = flash.error("A draft needs a title");
+        return Ok((flash, Redirect::to("/admin/drafts/new")).into_response());
+    }
+
+    let newsletter_issue_id = insert_draft(&app_state.db_pool, &draft_data)
+        .await
+        .context("Failed to store the draft")
+        .map_err(e500)?;
+
+    let flash = flash.info(DRAFT_SAVED_INFO_MESSAGE);
+    let location = format!("/admin/drafts/{}/edit", newsletter_issue_id);
+    Ok((flash, Redirect::to(&location)).into_response())
+}
+
+// edit draft handler
+#[tracing::instrument(
+name = "Edit a draft",
+skip(flash, draft_data, app_state, user_id),
+fields(user_id=%*user_id)
+)]
+pub async fn edit_draft(
+    Extension(user_id): Extension<UserId>,
+    flash: Flash,
+    State(app_state): State<AppState>,
+    Path(newsletter_issue_id): Path<Uuid>,
+    draft_data: Form<DraftData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let location = format!("/admin/drafts/{}/edit", newsletter_issue_id);
+    if draft_data.validate().is_err() {
+        let flash = flash.error("A draft needs a title");
+        return Ok((flash, Redirect::to(&location)).into_response());
+    }
+
+    let updated = update_draft(&app_state.db_pool, newsletter_issue_id, &draft_data)
+        .await
+        .context("Failed to update the draft")
+        .map_err(e500)?;
+    if !updated {
+        return Err(e404(anyhow::anyhow!(
+            "There is no draft with the provided id."
+        )));
+    }
+
+    let flash = flash.info(DRAFT_SAVED_INFO_MESSAGE);
+    Ok((flash, Redirect::to(&location)).into_response())
+}
+
+// delete draft handler
+#[tracing::instrument(
+name = "Delete a draft",
+skip(flash, app_state, user_id),
+fields(user_id=%*user_id)
+)]
+pub async fn remove_draft(
+    Extension(user_id): Extension<UserId>,
+    flash: Flash,
+    State(app_state): State<AppState>,
+    Path(newsletter_issue_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let deleted = delete_draft(&app_state.db_pool, newsletter_issue_id)
+        .await
+        .context("Failed to delete the draft")
+        .map_err(e500)?;
+    if !deleted {
+        return Err(e404(anyhow::anyhow!(
+            "There is no draft with the provided id."
+        )));
+    }
+
+    let flash = flash.info(DRAFT_DELETED_INFO_MESSAGE);
+    Ok((flash, Redirect::to("/admin/drafts")).into_response())
+}
+
+// publish draft handler
+#[tracing::instrument(
+name = "Publish a draft",
+skip(flash, publish_data, app_state, user_id),
+fields(user_id=%*user_id)
+)]
+pub async fn publish_draft(
+    Extension(user_id): Extension<UserId>,
+    flash: Flash,
+    State(app_state): State<AppState>,
+    Path(newsletter_issue_id): Path<Uuid>,
+    publish_data: Form<PublishDraftData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    // convert the incoming idempotency key received from the publish form data into our IdempotencyKey type
+    let idempotency_key: IdempotencyKey =
+        publish_data.0.idempotency_key.try_into().map_err(e400)?;
+
+    // call the try_processing function to deal with concurrent requests
+    let mut transaction = match try_processing(&app_state.db_pool, &idempotency_key, *user_id)
+        .await
+        .map_err(e500)?
+    {
+        NextAction::StartProcessing(t) => t,
+        NextAction::ReturnSavedResponse(saved_response) => return Ok(saved_response),
+    };
+
+    // lock the draft so that it can't be edited or published twice while we work on it
+    let draft = get_draft_for_update(&mut transaction, newsletter_issue_id)
+        .await
+        .context("Failed to retrieve the draft")
+        .map_err(e500)?;
+    // a draft which can't be published is sent back without saving the response, dropping the transaction
+    // releases the idempotency key, so that the request can be made again once the draft is fixed
+    let draft = match draft {
+        Some(draft) => draft,
+        None => {
+            let flash = flash.error("There is no draft with the provided id.");
+            return Ok((flash, Redirect::to("/admin/drafts")).into_response());
+        }
+    };
+    let location = format!("/admin/drafts/{}/edit", newsletter_issue_id);
+    if draft.validate().is_err() {
+        let flash = flash.error("Part of the draft has less than 5 characters");
+        return Ok((flash, Redirect::to(&location)).into_response());
+    }
+    if let Err(e) = draft.validate_placeholders() {
+        let flash = flash.error(e);
+        return Ok((flash, Redirect::to(&location)).into_response());
+    }
+
+    let slug = IssueSlug::new(&draft.title, newsletter_issue_id);
+    mark_draft_as_published(&mut transaction, newsletter_issue_id, &slug)
+        .await
+        .context("Failed to publish the draft")
+        .map_err(e500)?;
+    enqueue_delivery_tasks(&mut transaction, newsletter_issue_id)
+        .await
+        .context("Failed to enqueue delivery tasks")
+        .map_err(e500)?;
+    let flash = flash.info(PUBLISH_SUCCESS_INFO_MESSAGE);
+    let response = (flash, Redirect::to("/admin/drafts")).into_response();
+
+    // save the response so that retries of this request don't publish the draft twice
+    let response = save_response(transaction, &idempotency_key, *user_id, response)
+        .await
+        .map_err(e500)?;
+    Ok((response).into_response())
+}
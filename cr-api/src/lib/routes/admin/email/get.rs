@@ -0,0 +1,35 @@
+// src/routes/admin/email/get.rs
+
+// dependencies
+use crate::authentication::UserId;
+use crate::domain::AdminEmailTemplate;
+use crate::errors::{e500, ResponseError};
+use crate::routes::admin::dashboard::get_user_email;
+use crate::state::AppState;
+use axum::{extract::State, Extension};
+use axum_flash::IncomingFlashes;
+use std::fmt::Write;
+
+// handler to render the form where the logged in user sets the address test emails are sent to
+#[tracing::instrument(name = "Admin email form", skip(flashes, app_state, user_id))]
+pub async fn admin_email_form(
+    Extension(user_id): Extension<UserId>,
+    flashes: IncomingFlashes,
+    State(app_state): State<AppState>,
+) -> Result<(IncomingFlashes, AdminEmailTemplate), ResponseError> {
+    // process any incoming flash messages and convert them to a string for rendering
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    let email = get_user_email(*user_id, &app_state.db_pool)
+        .await
+        .map_err(e500)?
+        .unwrap_or_default();
+
+    // render the email form, prefilled with the current address
+    let admin_email_template = AdminEmailTemplate { flash_msg, email };
+
+    Ok((flashes, admin_email_template))
+}
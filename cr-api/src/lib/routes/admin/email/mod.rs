@@ -0,0 +1,7 @@
+// src/lib/routes/admin/email/mod.rs
+
+mod get;
+mod post;
+
+pub use get::admin_email_form;
+pub use post::change_admin_email;
@@ -0,0 +1,66 @@
+// src/routes/admin/email/post.rs
+
+// dependencies
+use crate::authentication::UserId;
+use crate::domain::SubscriberEmail;
+use crate::errors::{e500, ResponseError};
+use crate::state::AppState;
+use anyhow::Context;
+use axum::{
+    extract::{Form, State},
+    response::{IntoResponse, Redirect},
+    Extension,
+};
+use axum_flash::Flash;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct AdminEmailData {
+    email: String,
+}
+
+// function which stores the address test emails are sent to
+#[tracing::instrument(name = "Change user email", skip(pool))]
+async fn set_user_email(
+    user_id: Uuid,
+    email: &SubscriberEmail,
+    pool: &PgPool,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET email = $1
+        WHERE user_id = $2
+        "#,
+        email.as_ref(),
+        user_id,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to change the user's email in the database.")?;
+    Ok(())
+}
+
+// change admin email handler
+pub async fn change_admin_email(
+    flash: Flash,
+    Extension(user_id): Extension<UserId>,
+    State(app_state): State<AppState>,
+    email_data: Form<AdminEmailData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let email = match SubscriberEmail::parse(email_data.0.email) {
+        Ok(email) => email,
+        Err(e) => {
+            let flash = flash.error(e);
+            return Ok((flash, Redirect::to("/admin/email")).into_response());
+        }
+    };
+
+    set_user_email(*user_id, &email, &app_state.db_pool)
+        .await
+        .map_err(e500)?;
+    let flash = flash.info("Your email address has been changed.");
+    Ok((flash, Redirect::to("/admin/email")).into_response())
+}
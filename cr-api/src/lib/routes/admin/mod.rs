@@ -1,11 +1,19 @@
 // src/lib/routes/admin/mod.rs
 
 mod dashboard;
+mod drafts;
+mod email;
 mod logout;
 mod newsletter;
 mod password;
+mod subscribers;
+mod suppressions;
 
 pub use dashboard::admin_dashboard;
+pub use drafts::*;
+pub use email::*;
 pub use logout::log_out;
 pub use newsletter::*;
 pub use password::*;
+pub use subscribers::*;
+pub use suppressions::*;
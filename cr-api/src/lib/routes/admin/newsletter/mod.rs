@@ -2,6 +2,10 @@
 
 pub mod get;
 pub mod post;
+pub mod status;
+pub mod test;
 
 pub use get::publish_newsletter_form;
 pub use post::publish_newsletter;
+pub use status::newsletter_issue_status;
+pub use test::send_test_issue;
@@ -2,9 +2,11 @@
 
 // dependencies
 use crate::authentication::UserId;
+use crate::domain::{validate_placeholders, IssueSlug, NewsletterMarkdown};
 use crate::errors::{e400, e500, ResponseError};
 use crate::idempotency::IdempotencyKey;
 use crate::idempotency::{save_response, try_processing, NextAction};
+use crate::issue_delivery_worker::enqueue_delivery_tasks;
 use crate::state::AppState;
 use anyhow::Context;
 use axum::{
@@ -13,6 +15,7 @@ use axum::{
     Extension,
 };
 use axum_flash::Flash;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::Deserialize;
 use sqlx::{Executor, Postgres, Transaction};
 use uuid::Uuid;
@@ -24,24 +27,78 @@ pub struct NewsletterData {
     #[validate(length(min = 5))]
     title: String,
     #[validate(length(min = 5))]
+    #[serde(default)]
     text_content: String,
     #[validate(length(min = 5))]
+    #[serde(default)]
     html_content: String,
+    markdown_content: Option<String>,
+    scheduled_for: Option<String>,
     idempotency_key: String,
 }
 
 pub static PUBLISH_SUCCESS_INFO_MESSAGE: &str =
     "The newsletter issue has been accepted - emails will go out shortly.";
 
-// a function which publishes a newsletter issue
+pub static SCHEDULE_SUCCESS_INFO_MESSAGE: &str =
+    "The newsletter issue has been scheduled - emails will go out at the scheduled time.";
+
+// a function which parses the optional publishing time received from the form, interpreted as UTC
+fn parse_scheduled_for(scheduled_for: Option<&str>) -> Result<Option<DateTime<Utc>>, String> {
+    let scheduled_for = match scheduled_for.map(str::trim) {
+        None | Some("") => return Ok(None),
+        Some(s) => s,
+    };
+    let parsed = DateTime::parse_from_rfc3339(scheduled_for)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(scheduled_for, "%Y-%m-%dT%H:%M")
+                .or_else(|_| NaiveDateTime::parse_from_str(scheduled_for, "%Y-%m-%dT%H:%M:%S"))
+                .map(|dt| dt.and_utc())
+        })
+        .map_err(|_| format!("{} is not a valid publishing time.", scheduled_for))?;
+    if parsed <= Utc::now() {
+        return Err("The publishing time must be in the future.".to_string());
+    }
+    Ok(Some(parsed))
+}
+
+// a function which replaces the html and plain text content with the rendered Markdown source, if there is one;
+// the form always sends the Markdown field, left empty when the issue is written in html and plain text
+fn render_markdown(
+    newsletter_data: &mut NewsletterData,
+) -> Result<Option<NewsletterMarkdown>, String> {
+    let markdown_content = match newsletter_data.markdown_content.take() {
+        None => return Ok(None),
+        Some(s) if s.is_empty() => return Ok(None),
+        Some(s) => NewsletterMarkdown::parse(s)?,
+    };
+    newsletter_data.html_content = markdown_content.to_html();
+    newsletter_data.text_content = markdown_content.to_text();
+    Ok(Some(markdown_content))
+}
+
+// a function which stores a newsletter issue, either published straight away or scheduled for later; the
+// slug of the archive page is only given out once the issue is published
 #[tracing::instrument(skip_all)]
 async fn insert_newsletter_issue(
     transaction: &mut Transaction<'_, Postgres>,
     title: &str,
     text_content: &str,
     html_content: &str,
+    markdown_content: Option<&NewsletterMarkdown>,
+    scheduled_for: Option<DateTime<Utc>>,
 ) -> Result<Uuid, sqlx::Error> {
     let newsletter_issue_id = Uuid::new_v4();
+    let now = Utc::now();
+    let (status, published_at, slug) = match scheduled_for {
+        Some(_) => ("scheduled", None, None),
+        None => (
+            "published",
+            Some(now),
+            Some(IssueSlug::new(title, newsletter_issue_id)),
+        ),
+    };
     let query = sqlx::query!(
         r#"
         INSERT INTO newsletter_issues (
@@ -49,41 +106,31 @@ async fn insert_newsletter_issue(
             title,
             text_content,
             html_content,
-            published_at
+            markdown_content,
+            status,
+            scheduled_for,
+            published_at,
+            slug,
+            created_at,
+            updated_at
         )
-        VALUES ($1, $2, $3, $4, now())
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $10)
         "#,
         newsletter_issue_id,
         title,
         text_content,
-        html_content
+        html_content,
+        markdown_content.map(AsRef::as_ref),
+        status,
+        scheduled_for,
+        published_at,
+        slug.as_ref().map(AsRef::as_ref),
+        now
     );
     transaction.execute(query).await?;
     Ok(newsletter_issue_id)
 }
 
-// a function to queue delivery tasks
-#[tracing::instrument(skip_all)]
-async fn enqueue_delivery_tasks(
-    transaction: &mut Transaction<'_, Postgres>,
-    newsletter_issue_id: Uuid,
-) -> Result<(), sqlx::Error> {
-    let query = sqlx::query!(
-        r#"
-        INSERT INTO issue_delivery_queue (
-            newsletter_issue_id,
-            subscriber_email
-        )
-        SELECT $1, email
-        FROM subscriptions
-        WHERE status = 'confirmed'
-        "#,
-        newsletter_issue_id,
-    );
-    transaction.execute(query).await?;
-    Ok(())
-}
-
 // publish newsletter handler
 #[tracing::instrument(
 name = "Publish a newsletter issue",
@@ -94,13 +141,22 @@ pub async fn publish_newsletter(
     Extension(user_id): Extension<UserId>,
     flash: Flash,
     State(app_state): State<AppState>,
-    newsletter_data: Form<NewsletterData>,
+    Form(mut newsletter_data): Form<NewsletterData>,
 ) -> Result<impl IntoResponse, ResponseError> {
+    // issues authored in Markdown get their html and plain text content rendered from the single source
+    let markdown_content = match render_markdown(&mut newsletter_data) {
+        Ok(markdown_content) => markdown_content,
+        Err(e) => {
+            let flash = flash.error(e);
+            return Ok((flash, Redirect::to("/admin/newsletter")).into_response());
+        }
+    };
+
     // validate the form data
     let validated_data = match newsletter_data.validate() {
         Ok(_) => {
             tracing::trace!("Successfully extracted form body.");
-            &newsletter_data
+            newsletter_data
         }
         Err(e) => {
             tracing::trace!("Unable to extract form body: {:?}", e);
@@ -109,13 +165,30 @@ pub async fn publish_newsletter(
         }
     };
 
-    // destructure the validated data and idempotency key (to make the borrow checker happy)
+    // destructure the validated data and idempotency key
     let NewsletterData {
         title,
         html_content,
         text_content,
+        scheduled_for,
         idempotency_key,
-    } = validated_data.0.clone();
+        ..
+    } = validated_data;
+
+    // only the known placeholders can be expanded for each subscriber, reject anything else before storing the issue
+    if let Err(e) = validate_placeholders(&html_content, &text_content) {
+        let flash = flash.error(e);
+        return Ok((flash, Redirect::to("/admin/newsletter")).into_response());
+    }
+
+    // check the optional publishing time before doing any work
+    let scheduled_for = match parse_scheduled_for(scheduled_for.as_deref()) {
+        Ok(scheduled_for) => scheduled_for,
+        Err(e) => {
+            let flash = flash.error(e);
+            return Ok((flash, Redirect::to("/admin/newsletter")).into_response());
+        }
+    };
 
     // convert the incoming idempotency key received from the newsletter form data into our IdempotencyKey type
     let idempotency_key: IdempotencyKey = idempotency_key.try_into().map_err(e400)?;
@@ -126,24 +199,34 @@ pub async fn publish_newsletter(
         .map_err(e500)?
     {
         NextAction::StartProcessing(t) => t,
-        NextAction::ReturnSavedResponse(saved_response) => {
-            let flash = flash.info(PUBLISH_SUCCESS_INFO_MESSAGE);
-            return Ok((flash, saved_response).into_response());
-        }
+        NextAction::ReturnSavedResponse(saved_response) => return Ok(saved_response),
     };
 
-    let issue_id = insert_newsletter_issue(&mut transaction, &title, &text_content, &html_content)
-        .await
-        .context("Failed to store newsletter issue details")
-        .map_err(e500)?;
+    let issue_id = insert_newsletter_issue(
+        &mut transaction,
+        &title,
+        &text_content,
+        &html_content,
+        markdown_content.as_ref(),
+        scheduled_for,
+    )
+    .await
+    .context("Failed to store newsletter issue details")
+    .map_err(e500)?;
 
-    enqueue_delivery_tasks(&mut transaction, issue_id)
-        .await
-        .context("Failed to enqueue delivery tasks")
-        .map_err(e500)?;
+    // scheduled issues are picked up by the scheduling worker once they are due
+    let success_message = if scheduled_for.is_none() {
+        enqueue_delivery_tasks(&mut transaction, issue_id)
+            .await
+            .context("Failed to enqueue delivery tasks")
+            .map_err(e500)?;
+        PUBLISH_SUCCESS_INFO_MESSAGE
+    } else {
+        SCHEDULE_SUCCESS_INFO_MESSAGE
+    };
 
     // build and send the success response message after the newsletter issue has been published
-    let flash = flash.info(PUBLISH_SUCCESS_INFO_MESSAGE);
+    let flash = flash.info(success_message);
     let response = (flash, Redirect::to("/admin/newsletter")).into_response();
     let response = save_response(transaction, &idempotency_key, *user_id, response)
         .await
@@ -0,0 +1,142 @@
+// src/routes/admin/newsletter/status.rs
+
+// dependencies
+use crate::domain::{IssueDelivery, NewsletterIssueStatusTemplate};
+use crate::errors::{e404, e500, ResponseError};
+use crate::issue_delivery_worker::DeliveryStatus;
+use crate::state::AppState;
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum_flash::IncomingFlashes;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::fmt::Write;
+use uuid::Uuid;
+
+// a struct to represent the newsletter issue details shown on the status page
+struct IssueSummary {
+    title: String,
+    published_at: Option<DateTime<Utc>>,
+}
+
+// function to format a timestamp for display on the status page
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+// function which retrieves the title and publication date of a newsletter issue
+#[tracing::instrument(name = "Get newsletter issue summary", skip(pool))]
+async fn get_issue_summary(
+    pool: &PgPool,
+    newsletter_issue_id: Uuid,
+) -> Result<Option<IssueSummary>, anyhow::Error> {
+    let issue = sqlx::query_as!(
+        IssueSummary,
+        r#"
+        SELECT title, published_at
+        FROM newsletter_issues
+        WHERE newsletter_issue_id = $1
+        "#,
+        newsletter_issue_id
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to perform a query to retrieve the newsletter issue.")?;
+    Ok(issue)
+}
+
+// function which retrieves the logged deliveries and the still queued deliveries for a newsletter issue
+#[tracing::instrument(name = "Get newsletter issue deliveries", skip(pool))]
+async fn get_issue_deliveries(
+    pool: &PgPool,
+    newsletter_issue_id: Uuid,
+) -> Result<Vec<IssueDelivery>, anyhow::Error> {
+    let logged = sqlx::query!(
+        r#"
+        SELECT subscriber_email, status, error, recorded_at
+        FROM newsletter_issue_deliveries
+        WHERE newsletter_issue_id = $1
+        ORDER BY subscriber_email
+        "#,
+        newsletter_issue_id
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to perform a query to retrieve the logged deliveries.")?;
+
+    let queued = sqlx::query!(
+        r#"
+        SELECT subscriber_email, n_attempts, next_attempt_at
+        FROM issue_delivery_queue
+        WHERE newsletter_issue_id = $1
+        ORDER BY subscriber_email
+        "#,
+        newsletter_issue_id
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to perform a query to retrieve the queued deliveries.")?;
+
+    let logged = logged.into_iter().map(|r| IssueDelivery {
+        subscriber_email: r.subscriber_email,
+        status: r.status,
+        recorded_at: format_timestamp(r.recorded_at),
+        error: r.error.unwrap_or_default(),
+    });
+    let queued = queued.into_iter().map(|r| IssueDelivery {
+        subscriber_email: r.subscriber_email,
+        status: "pending".to_string(),
+        recorded_at: format_timestamp(r.next_attempt_at),
+        error: if r.n_attempts > 0 {
+            format!("{} failed attempt(s), retry scheduled", r.n_attempts)
+        } else {
+            String::new()
+        },
+    });
+    Ok(logged.chain(queued).collect())
+}
+
+// handler to render the delivery status page for a single newsletter issue
+#[tracing::instrument(name = "Newsletter issue status", skip(flashes, app_state))]
+pub async fn newsletter_issue_status(
+    flashes: IncomingFlashes,
+    State(app_state): State<AppState>,
+    Path(newsletter_issue_id): Path<Uuid>,
+) -> Result<(IncomingFlashes, NewsletterIssueStatusTemplate), ResponseError> {
+    // process any incoming flash messages
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    // look up the issue, bail out with a 404 if it doesn't exist
+    let issue = get_issue_summary(&app_state.db_pool, newsletter_issue_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| anyhow::anyhow!("There is no newsletter issue with the provided id."))
+        .map_err(e404)?;
+
+    let deliveries = get_issue_deliveries(&app_state.db_pool, newsletter_issue_id)
+        .await
+        .map_err(e500)?;
+    let count = |status: &str| deliveries.iter().filter(|d| d.status == status).count();
+
+    // render the delivery status page from its associated Askama template
+    let newsletter_issue_status_template = NewsletterIssueStatusTemplate {
+        flash_msg,
+        newsletter_issue_id,
+        title: issue.title,
+        published_at: issue
+            .published_at
+            .map(format_timestamp)
+            .unwrap_or_else(|| "not published yet".to_string()),
+        n_sent: count(DeliveryStatus::Sent.as_str()),
+        n_failed: count(DeliveryStatus::Failed.as_str()),
+        n_skipped: count(DeliveryStatus::SkippedInvalidEmail.as_str()),
+        n_unsubscribed: count(DeliveryStatus::SkippedUnsubscribed.as_str()),
+        n_pending: count("pending"),
+        deliveries,
+    };
+
+    Ok((flashes, newsletter_issue_status_template))
+}
@@ -0,0 +1,102 @@
+// src/routes/admin/newsletter/test.rs
+
+// dependencies
+use crate::authentication::UserId;
+use crate::domain::{Personalization, SubscriberEmail};
+use crate::errors::{e404, e500, ResponseError};
+use crate::issue_delivery_worker::{get_issue, send_issue, unsubscribe_link};
+use crate::routes::admin::dashboard::{get_user_email, get_username};
+use crate::state::AppState;
+use anyhow::Context;
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Redirect},
+    Extension,
+};
+use axum_flash::Flash;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub static TEST_EMAIL_SENT_INFO_MESSAGE: &str = "A test email has been sent to your email address.";
+
+// function which retrieves the status of a newsletter issue, if it exists
+#[tracing::instrument(name = "Get newsletter issue status", skip(pool))]
+async fn get_issue_status(
+    pool: &PgPool,
+    newsletter_issue_id: Uuid,
+) -> Result<Option<String>, anyhow::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT status
+        FROM newsletter_issues
+        WHERE newsletter_issue_id = $1
+        "#,
+        newsletter_issue_id
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to perform a query to retrieve the newsletter issue.")?;
+    Ok(row.map(|r| r.status))
+}
+
+// send test email handler, delivers a single copy of an issue to the logged in user and nobody else
+#[tracing::instrument(
+name = "Send a test email of a newsletter issue",
+skip(flash, app_state, user_id),
+fields(user_id=%*user_id)
+)]
+pub async fn send_test_issue(
+    Extension(user_id): Extension<UserId>,
+    flash: Flash,
+    State(app_state): State<AppState>,
+    Path(newsletter_issue_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let status = get_issue_status(&app_state.db_pool, newsletter_issue_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| anyhow::anyhow!("There is no newsletter issue with the provided id."))
+        .map_err(e404)?;
+
+    // drafts are tested from their preview page, everything else from its delivery status page
+    let location = if status == "draft" {
+        format!("/admin/drafts/{}/preview", newsletter_issue_id)
+    } else {
+        format!("/admin/newsletter/{}/status", newsletter_issue_id)
+    };
+
+    let email = get_user_email(*user_id, &app_state.db_pool)
+        .await
+        .map_err(e500)?
+        .and_then(|email| SubscriberEmail::parse(email).ok());
+    let Some(email) = email else {
+        let flash = flash.error("Set your email address before sending a test email.");
+        return Ok((flash, Redirect::to(&location)).into_response());
+    };
+
+    // render the issue exactly as the delivery worker does, personalised for the admin; the unsubscribe link
+    // doesn't belong to any subscriber
+    let issue = get_issue(&app_state.db_pool, newsletter_issue_id)
+        .await
+        .map_err(e500)?;
+    let username = get_username(*user_id, &app_state.db_pool)
+        .await
+        .map_err(e500)?;
+    let unsubscribe_link = unsubscribe_link(&app_state.bs_url, &app_state.hmac_secret, Uuid::nil());
+    let personalization = Personalization {
+        name: &username,
+        email: email.as_ref(),
+        unsubscribe_url: &unsubscribe_link,
+    };
+    let flash = match send_issue(&app_state.em_client, &email, &issue, &personalization).await {
+        Ok(()) => flash.info(TEST_EMAIL_SENT_INFO_MESSAGE),
+        Err(e) => {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "Failed to send a test email.",
+            );
+            flash.error("Failed to send the test email, please try again later.")
+        }
+    };
+    Ok((flash, Redirect::to(&location)).into_response())
+}
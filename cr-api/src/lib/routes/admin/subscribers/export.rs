@@ -0,0 +1,105 @@
+// src/routes/admin/subscribers/export.rs
+
+// dependencies
+use crate::authentication::UserId;
+use crate::state::AppState;
+use anyhow::Context;
+use axum::{
+    body::Body,
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use chrono::Utc;
+use futures_util::{Stream, TryStreamExt};
+use sqlx::PgPool;
+use std::borrow::Cow;
+
+// the columns of the export, the import reads the email and name columns back
+const EXPORT_HEADER: [&str; 4] = ["email", "name", "status", "subscribed_at"];
+// the characters a spreadsheet reads a formula from, when they start a cell
+const FORMULA_PREFIXES: [char; 4] = ['=', '+', '-', '@'];
+
+// function to keep a field typed in by a subscriber from being run as a formula once the export is opened in a
+// spreadsheet, a field which would start one is prefixed with a quote
+pub(super) fn escape_formula(field: &str) -> Cow<'_, str> {
+    if field.starts_with(FORMULA_PREFIXES) {
+        Cow::Owned(format!("'{}", field))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+// function to undo escape_formula, so that an export can be imported again as it was
+pub(super) fn unescape_formula(field: &str) -> &str {
+    match field.strip_prefix('\'') {
+        Some(formula) if formula.starts_with(FORMULA_PREFIXES) => formula,
+        _ => field,
+    }
+}
+
+// function to write a single CSV record, quoting the fields which need it
+fn csv_record(fields: &[&str]) -> Result<Vec<u8>, anyhow::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(fields)?;
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())
+        .context("Failed to write a CSV record.")
+}
+
+// function which streams every subscriber as a CSV record, oldest first, after the header; the rows are
+// read from the database as the client downloads them, so a large list is never held in memory
+fn subscriber_records(pool: PgPool) -> impl Stream<Item = Result<Vec<u8>, anyhow::Error>> {
+    async_stream::try_stream! {
+        yield csv_record(&EXPORT_HEADER)?;
+        let mut subscribers = sqlx::query!(
+            r#"
+            SELECT email, name, status, subscribed_at
+            FROM subscriptions
+            ORDER BY subscribed_at, email
+            "#
+        )
+        .fetch(&pool);
+        while let Some(subscriber) = subscribers
+            .try_next()
+            .await
+            .context("Failed to perform a query to retrieve the subscribers.")?
+        {
+            yield csv_record(&[
+                &escape_formula(&subscriber.email),
+                &escape_formula(&subscriber.name),
+                &subscriber.status,
+                &subscriber.subscribed_at.to_rfc3339(),
+            ])?;
+        }
+    }
+}
+
+// export subscribers handler, the list is sent as a CSV file download
+#[tracing::instrument(
+name = "Export subscribers",
+skip(app_state, user_id),
+fields(user_id=%*user_id)
+)]
+pub async fn export_subscribers(
+    Extension(user_id): Extension<UserId>,
+    State(app_state): State<AppState>,
+) -> Response {
+    let records = subscriber_records(app_state.db_pool.clone()).inspect_err(
+        |e| tracing::error!(error.cause_chain = ?e, "The subscriber export was cut short."),
+    );
+    let content_disposition = format!(
+        "attachment; filename=\"subscribers-{}.csv\"",
+        Utc::now().format("%Y-%m-%d")
+    );
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, content_disposition),
+        ],
+        Body::from_stream(records),
+    )
+        .into_response()
+}
@@ -0,0 +1,196 @@
+// src/routes/admin/subscribers/get.rs
+
+// dependencies
+use crate::audit_log::get_recent_admin_actions;
+use crate::domain::{AdminActionRow, StatusOption, SubscriberRow, SubscribersTemplate};
+use crate::errors::{e400, e500, ResponseError};
+use crate::state::AppState;
+use anyhow::Context;
+use axum::extract::{Query, State};
+use axum_flash::IncomingFlashes;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::fmt::Write;
+
+// the number of subscribers listed on each page
+const SUBSCRIBERS_PER_PAGE: i64 = 25;
+// the number of audit log entries shown below the subscriber list
+const RECENT_ADMIN_ACTIONS: i64 = 20;
+// the states a subscription can be in, which the list can be filtered by
+const SUBSCRIPTION_STATUSES: [&str; 5] = [
+    "pending_confirmation",
+    "confirmed",
+    "unsubscribed",
+    "bounced",
+    "complained",
+];
+
+// struct to represent the query parameters of the subscriber list, every one of them is optional
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SubscribersParameters {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    search: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<i64>,
+}
+
+// function to turn a search into a case-insensitive pattern matching it anywhere, LIKE wildcards typed in
+// the search box are matched as they are
+fn search_pattern(search: &str) -> Option<String> {
+    let search = search.trim();
+    if search.is_empty() {
+        return None;
+    }
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    Some(format!("%{}%", escaped))
+}
+
+// function which retrieves a page of the subscribers matching the search and status filter, most recent first,
+// starting at the given offset, along with the number of matching subscribers
+#[tracing::instrument(name = "Get subscribers", skip(pool))]
+async fn get_subscribers(
+    pool: &PgPool,
+    search: Option<&str>,
+    status: Option<&str>,
+    offset: i64,
+) -> Result<(Vec<SubscriberRow>, i64), anyhow::Error> {
+    let n_matching = sqlx::query!(
+        r#"
+        SELECT COUNT(*) as "value!"
+        FROM subscriptions
+        WHERE
+            ($1::text IS NULL OR email ILIKE $1 OR name ILIKE $1) AND
+            ($2::text IS NULL OR status = $2)
+        "#,
+        search,
+        status,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to perform a query to count the subscribers.")?
+    .value;
+    let subscribers = sqlx::query!(
+        r#"
+        SELECT id, email, name, status, subscribed_at
+        FROM subscriptions
+        WHERE
+            ($1::text IS NULL OR email ILIKE $1 OR name ILIKE $1) AND
+            ($2::text IS NULL OR status = $2)
+        ORDER BY subscribed_at DESC, email
+        LIMIT $3 OFFSET $4
+        "#,
+        search,
+        status,
+        SUBSCRIBERS_PER_PAGE,
+        offset,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to perform a query to retrieve the subscribers.")?
+    .into_iter()
+    .map(|r| SubscriberRow {
+        id: r.id,
+        email: r.email,
+        name: r.name,
+        status: r.status,
+        subscribed_at: r.subscribed_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+    })
+    .collect();
+    Ok((subscribers, n_matching))
+}
+
+// handler to render the subscriber list, with the search and status filter and the recent admin actions
+#[tracing::instrument(name = "Subscribers", skip(flashes, app_state))]
+pub async fn subscribers(
+    flashes: IncomingFlashes,
+    State(app_state): State<AppState>,
+    parameters: Query<SubscribersParameters>,
+) -> Result<(IncomingFlashes, SubscribersTemplate), ResponseError> {
+    // process any incoming flash messages
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    let SubscribersParameters {
+        search,
+        status,
+        page,
+    } = parameters.0;
+    let status = match status.as_str() {
+        "" => None,
+        status if SUBSCRIPTION_STATUSES.contains(&status) => Some(status),
+        status => {
+            return Err(e400(anyhow::anyhow!(
+                "{} is not a valid subscription status.",
+                status
+            )))
+        }
+    };
+    let page = page.unwrap_or(1).max(1);
+    let offset = (page - 1)
+        .checked_mul(SUBSCRIBERS_PER_PAGE)
+        .ok_or_else(|| e400(anyhow::anyhow!("{} is not a valid page number.", page)))?;
+    let (subscribers, n_matching) = get_subscribers(
+        &app_state.db_pool,
+        search_pattern(&search).as_deref(),
+        status,
+        offset,
+    )
+    .await
+    .map_err(e500)?;
+
+    // the page links keep the search and the status filter
+    let page_link = |page: i64| {
+        let parameters = SubscribersParameters {
+            search: search.clone(),
+            status: status.unwrap_or_default().to_string(),
+            page: Some(page),
+        };
+        format!(
+            "/admin/subscribers?{}",
+            serde_urlencoded::to_string(parameters).unwrap()
+        )
+    };
+    let newer_page_link = (page > 1).then(|| page_link(page - 1));
+    let older_page_link =
+        (offset.saturating_add(SUBSCRIBERS_PER_PAGE) < n_matching).then(|| page_link(page + 1));
+
+    let admin_actions = get_recent_admin_actions(&app_state.db_pool, RECENT_ADMIN_ACTIONS)
+        .await
+        .context("Failed to perform a query to retrieve the recent admin actions.")
+        .map_err(e500)?
+        .into_iter()
+        .map(|entry| AdminActionRow {
+            recorded_at: entry.recorded_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+            username: entry.username,
+            action: entry.action,
+            subscriber_email: entry.subscriber_email,
+        })
+        .collect();
+
+    // render the subscriber list from its associated Askama template
+    let subscribers_template = SubscribersTemplate {
+        flash_msg,
+        status_options: SUBSCRIPTION_STATUSES
+            .iter()
+            .map(|value| StatusOption {
+                value,
+                selected: status == Some(*value),
+            })
+            .collect(),
+        search,
+        n_matching,
+        subscribers,
+        newer_page_link,
+        older_page_link,
+        admin_actions,
+    };
+
+    Ok((flashes, subscribers_template))
+}
@@ -0,0 +1,265 @@
+// src/routes/admin/subscribers/import.rs
+
+// dependencies
+use super::export::unescape_formula;
+use crate::audit_log::{record_admin_action, AdminAction};
+use crate::authentication::UserId;
+use crate::domain::{
+    ImportReport, ImportedRowIssue, NewSubscriber, SubscriberEmail, SubscriberName,
+    SubscribersImportTemplate,
+};
+use crate::errors::{e400, e500, ResponseError};
+use crate::routes::{enqueue_confirmation_email, generate_subscription_token, store_token};
+use crate::state::AppState;
+use crate::suppressions::is_suppressed;
+use anyhow::Context;
+use axum::{
+    extract::{Multipart, State},
+    response::{IntoResponse, Redirect, Response},
+    Extension,
+};
+use axum_flash::{Flash, IncomingFlashes};
+use sqlx::{Postgres, Transaction};
+use std::fmt::Write;
+use uuid::Uuid;
+
+// a struct to represent a row of the imported file, with its line number
+struct ImportedRow {
+    line: u64,
+    subscriber: Result<NewSubscriber, String>,
+}
+
+// function to read the rows of an imported CSV file; the email and name columns are found by their header,
+// so that the columns can come in any order and the other columns of an export are ignored, and the fields
+// an export escaped are read back as they were
+fn parse_csv(file: &[u8]) -> Result<Vec<ImportedRow>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(file);
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("The CSV file couldn't be read: {}.", e))?;
+    let column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let (email_column, name_column) = match (column("email"), column("name")) {
+        (Some(email_column), Some(name_column)) => (email_column, name_column),
+        _ => return Err("The CSV file needs a header row with an email and a name column.".into()),
+    };
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let row = match record {
+            Ok(record) => {
+                let field = |column: usize| {
+                    unescape_formula(record.get(column).unwrap_or_default()).to_string()
+                };
+                ImportedRow {
+                    line: record.position().map_or(0, |p| p.line()),
+                    subscriber: SubscriberEmail::parse(field(email_column)).and_then(|email| {
+                        let name = SubscriberName::parse(field(name_column))?;
+                        Ok(NewSubscriber { email, name })
+                    }),
+                }
+            }
+            Err(e) => ImportedRow {
+                line: e.position().map_or(0, |p| p.line()),
+                subscriber: Err(format!("The row couldn't be read: {}.", e)),
+            },
+        };
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+// function to insert an imported subscriber with the chosen status; an address which is already
+// subscribed is left alone, in which case no id is returned
+#[tracing::instrument(skip(transaction, new_subscriber))]
+async fn insert_imported_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    new_subscriber: &NewSubscriber,
+    status: &str,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let subscriber_id = sqlx::query!(
+        r#"
+        INSERT INTO subscriptions (id, email, name, subscribed_at, status)
+        VALUES ($1, $2, $3, now(), $4)
+        ON CONFLICT (email) DO NOTHING
+        RETURNING id
+        "#,
+        Uuid::new_v4(),
+        new_subscriber.email.as_ref(),
+        new_subscriber.name.as_ref(),
+        status,
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .map(|r| r.id);
+    Ok(subscriber_id)
+}
+
+// function to import the valid rows, in a single transaction, and report on every row left out; rows
+// imported as pending confirmation are sent a confirmation email through the outbox
+async fn import_rows(
+    app_state: &AppState,
+    user_id: Uuid,
+    rows: Vec<ImportedRow>,
+    status: &str,
+) -> Result<ImportReport, anyhow::Error> {
+    let mut report = ImportReport {
+        n_imported: 0,
+        imported_as: status.to_string(),
+        skipped: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut transaction = app_state
+        .db_pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool.")?;
+    for ImportedRow { line, subscriber } in rows {
+        let new_subscriber = match subscriber {
+            Ok(new_subscriber) => new_subscriber,
+            Err(message) => {
+                report.errors.push(ImportedRowIssue { line, message });
+                continue;
+            }
+        };
+        let email = new_subscriber.email.as_ref();
+        if is_suppressed(&app_state.db_pool, email)
+            .await
+            .context("Failed to check the suppression list.")?
+        {
+            report.skipped.push(ImportedRowIssue {
+                line,
+                message: format!("{} is on the suppression list.", email),
+            });
+            continue;
+        }
+        let subscriber_id =
+            match insert_imported_subscriber(&mut transaction, &new_subscriber, status)
+                .await
+                .context("Failed to insert an imported subscriber in the database.")?
+            {
+                Some(subscriber_id) => subscriber_id,
+                None => {
+                    report.skipped.push(ImportedRowIssue {
+                        line,
+                        message: format!("{} is already subscribed.", email),
+                    });
+                    continue;
+                }
+            };
+        if status == "pending_confirmation" {
+            let subscription_token = generate_subscription_token();
+            store_token(&mut transaction, subscriber_id, &subscription_token)
+                .await
+                .context("Failed to store the confirmation token for an imported subscriber.")?;
+            enqueue_confirmation_email(
+                &mut transaction,
+                &new_subscriber.email,
+                &app_state.bs_url.0,
+                &subscription_token,
+            )
+            .await
+            .context("Failed to queue a confirmation email.")?;
+        }
+        record_admin_action(
+            &mut transaction,
+            user_id,
+            AdminAction::ImportSubscriber,
+            subscriber_id,
+            email,
+        )
+        .await
+        .context("Failed to record the admin action in the audit log.")?;
+        report.n_imported += 1;
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the SQL transaction to import subscribers.")?;
+    Ok(report)
+}
+
+// import subscribers form handler
+#[tracing::instrument(name = "Import subscribers form", skip(flashes))]
+pub async fn import_subscribers_form(
+    flashes: IncomingFlashes,
+) -> (IncomingFlashes, SubscribersImportTemplate) {
+    // process any incoming flash messages
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    let import_template = SubscribersImportTemplate {
+        flash_msg,
+        report: None,
+    };
+
+    (flashes, import_template)
+}
+
+// import subscribers handler, the file comes from a multipart form along with the status to import as;
+// the report of the import is rendered right away, below the form
+#[tracing::instrument(
+name = "Import subscribers",
+skip(flash, app_state, user_id, multipart),
+fields(user_id=%*user_id)
+)]
+pub async fn import_subscribers(
+    Extension(user_id): Extension<UserId>,
+    flash: Flash,
+    State(app_state): State<AppState>,
+    mut multipart: Multipart,
+) -> Result<Response, ResponseError> {
+    let mut file = None;
+    let mut status = String::new();
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return Err(e400(e)),
+        };
+        match field.name() {
+            Some("file") => file = Some(field.bytes().await.map_err(e400)?),
+            Some("status") => status = field.text().await.map_err(e400)?,
+            _ => {}
+        }
+    }
+
+    let back_to_form = |flash: Flash, message: String| {
+        Ok((
+            flash.error(message),
+            Redirect::to("/admin/subscribers/import"),
+        )
+            .into_response())
+    };
+    if !["confirmed", "pending_confirmation"].contains(&status.as_str()) {
+        return back_to_form(
+            flash,
+            "Choose whether the subscribers are imported as confirmed or sent a confirmation email."
+                .into(),
+        );
+    }
+    let rows = match file
+        .filter(|file| !file.is_empty())
+        .map(|file| parse_csv(&file))
+    {
+        Some(Ok(rows)) => rows,
+        Some(Err(message)) => return back_to_form(flash, message),
+        None => return back_to_form(flash, "Choose a CSV file to import.".into()),
+    };
+
+    let report = import_rows(&app_state, *user_id, rows, &status)
+        .await
+        .map_err(e500)?;
+
+    // render the report from the associated Askama template
+    let import_template = SubscribersImportTemplate {
+        flash_msg: String::new(),
+        report: Some(report),
+    };
+
+    Ok(import_template.into_response())
+}
@@ -0,0 +1,11 @@
+// src/lib/routes/admin/subscribers/mod.rs
+
+mod export;
+mod get;
+mod import;
+mod post;
+
+pub use export::export_subscribers;
+pub use get::subscribers;
+pub use import::{import_subscribers, import_subscribers_form};
+pub use post::{admin_confirm_subscriber, admin_delete_subscriber, admin_unsubscribe_subscriber};
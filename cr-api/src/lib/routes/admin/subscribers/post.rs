@@ -0,0 +1,264 @@
+// src/routes/admin/subscribers/post.rs
+
+// dependencies
+use crate::audit_log::{record_admin_action, AdminAction};
+use crate::authentication::UserId;
+use crate::errors::{e500, ResponseError};
+use crate::state::AppState;
+use crate::suppressions::{is_suppressed, suppress, SuppressionSource};
+use anyhow::Context;
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Redirect},
+    Extension,
+};
+use axum_flash::Flash;
+use sqlx::{Executor, Postgres, Transaction};
+use uuid::Uuid;
+
+// an enum to represent the actions an admin can take on a single subscriber
+#[derive(Debug, Clone, Copy)]
+enum SubscriberAction {
+    Confirm,
+    Unsubscribe,
+    Delete,
+}
+
+// implementation to return the audit log action recorded for each subscriber action
+impl From<SubscriberAction> for AdminAction {
+    fn from(action: SubscriberAction) -> Self {
+        match action {
+            SubscriberAction::Confirm => AdminAction::ConfirmSubscriber,
+            SubscriberAction::Unsubscribe => AdminAction::UnsubscribeSubscriber,
+            SubscriberAction::Delete => AdminAction::DeleteSubscriber,
+        }
+    }
+}
+
+// a struct to represent the subscriber an admin action applies to
+struct ManagedSubscriber {
+    email: String,
+    status: String,
+}
+
+// function to look up a subscriber, locking the row for the rest of the transaction
+#[tracing::instrument(skip(transaction))]
+async fn get_subscriber_for_update(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+) -> Result<Option<ManagedSubscriber>, sqlx::Error> {
+    sqlx::query_as!(
+        ManagedSubscriber,
+        r#"SELECT email, status FROM subscriptions WHERE id = $1 FOR UPDATE"#,
+        subscriber_id,
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+}
+
+// function to set the status of a subscriber by hand; any confirmation link still out there is retired, so
+// that it can't undo the change
+#[tracing::instrument(skip(transaction))]
+async fn set_subscriber_status(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+    status: &str,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"UPDATE subscriptions SET status = $2 WHERE id = $1"#,
+        subscriber_id,
+        status,
+    );
+    transaction.execute(query).await?;
+    let query = sqlx::query!(
+        r#"
+        UPDATE subscription_tokens
+        SET consumed_at = now()
+        WHERE subscriber_id = $1 AND consumed_at IS NULL
+        "#,
+        subscriber_id,
+    );
+    transaction.execute(query).await?;
+    Ok(())
+}
+
+// function to delete a subscriber, along with their confirmation tokens
+#[tracing::instrument(skip(transaction))]
+async fn remove_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"DELETE FROM subscription_tokens WHERE subscriber_id = $1"#,
+        subscriber_id,
+    );
+    transaction.execute(query).await?;
+    let query = sqlx::query!(r#"DELETE FROM subscriptions WHERE id = $1"#, subscriber_id);
+    transaction.execute(query).await?;
+    Ok(())
+}
+
+// function to apply an admin action to a subscriber and record it in the audit log, in a single transaction;
+// returns the message to show the admin, an error message if there was nothing to do
+async fn manage_subscriber(
+    app_state: &AppState,
+    user_id: Uuid,
+    subscriber_id: Uuid,
+    action: SubscriberAction,
+) -> Result<Result<&'static str, &'static str>, anyhow::Error> {
+    let mut transaction = app_state
+        .db_pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool.")?;
+    let subscriber = match get_subscriber_for_update(&mut transaction, subscriber_id)
+        .await
+        .context("Failed to look up the subscriber.")?
+    {
+        Some(subscriber) => subscriber,
+        None => return Ok(Err("There is no subscriber with the provided id.")),
+    };
+
+    let message = match action {
+        SubscriberAction::Confirm if subscriber.status == "confirmed" => {
+            return Ok(Err("The subscriber is already confirmed."));
+        }
+        SubscriberAction::Unsubscribe if subscriber.status == "unsubscribed" => {
+            return Ok(Err("The subscriber is already unsubscribed."));
+        }
+        SubscriberAction::Confirm
+            if is_suppressed(&app_state.db_pool, &subscriber.email)
+                .await
+                .context("Failed to check the suppression list.")? =>
+        {
+            return Ok(Err(
+                "The subscriber's address is on the suppression list, it can't be confirmed.",
+            ));
+        }
+        SubscriberAction::Confirm => {
+            set_subscriber_status(&mut transaction, subscriber_id, "confirmed")
+                .await
+                .context("Failed to confirm the subscriber.")?;
+            "The subscriber has been confirmed."
+        }
+        SubscriberAction::Unsubscribe => {
+            set_subscriber_status(&mut transaction, subscriber_id, "unsubscribed")
+                .await
+                .context("Failed to unsubscribe the subscriber.")?;
+            // as when the subscriber unsubscribes themselves, signing up anew doesn't bring the address back
+            suppress(
+                &mut transaction,
+                &subscriber.email,
+                "unsubscribed by an admin",
+                SuppressionSource::Admin,
+            )
+            .await
+            .context("Failed to add the address to the suppression list.")?;
+            "The subscriber has been unsubscribed."
+        }
+        SubscriberAction::Delete => {
+            remove_subscriber(&mut transaction, subscriber_id)
+                .await
+                .context("Failed to delete the subscriber.")?;
+            "The subscriber has been deleted."
+        }
+    };
+    record_admin_action(
+        &mut transaction,
+        user_id,
+        action.into(),
+        subscriber_id,
+        &subscriber.email,
+    )
+    .await
+    .context("Failed to record the admin action in the audit log.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the SQL transaction to manage a subscriber.")?;
+    Ok(Ok(message))
+}
+
+// function to turn the outcome of an admin action into a flash message and a redirect to the subscriber list
+async fn respond(
+    flash: Flash,
+    app_state: &AppState,
+    user_id: Uuid,
+    subscriber_id: Uuid,
+    action: SubscriberAction,
+) -> Result<impl IntoResponse, ResponseError> {
+    let flash = match manage_subscriber(app_state, user_id, subscriber_id, action)
+        .await
+        .map_err(e500)?
+    {
+        Ok(message) => flash.info(message),
+        Err(message) => flash.error(message),
+    };
+    Ok((flash, Redirect::to("/admin/subscribers")).into_response())
+}
+
+// confirm subscriber handler
+#[tracing::instrument(
+name = "Confirm a subscriber by hand",
+skip(flash, app_state, user_id),
+fields(user_id=%*user_id)
+)]
+pub async fn admin_confirm_subscriber(
+    Extension(user_id): Extension<UserId>,
+    flash: Flash,
+    State(app_state): State<AppState>,
+    Path(subscriber_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ResponseError> {
+    respond(
+        flash,
+        &app_state,
+        *user_id,
+        subscriber_id,
+        SubscriberAction::Confirm,
+    )
+    .await
+}
+
+// unsubscribe subscriber handler
+#[tracing::instrument(
+name = "Unsubscribe a subscriber by hand",
+skip(flash, app_state, user_id),
+fields(user_id=%*user_id)
+)]
+pub async fn admin_unsubscribe_subscriber(
+    Extension(user_id): Extension<UserId>,
+    flash: Flash,
+    State(app_state): State<AppState>,
+    Path(subscriber_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ResponseError> {
+    respond(
+        flash,
+        &app_state,
+        *user_id,
+        subscriber_id,
+        SubscriberAction::Unsubscribe,
+    )
+    .await
+}
+
+// delete subscriber handler
+#[tracing::instrument(
+name = "Delete a subscriber",
+skip(flash, app_state, user_id),
+fields(user_id=%*user_id)
+)]
+pub async fn admin_delete_subscriber(
+    Extension(user_id): Extension<UserId>,
+    flash: Flash,
+    State(app_state): State<AppState>,
+    Path(subscriber_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ResponseError> {
+    respond(
+        flash,
+        &app_state,
+        *user_id,
+        subscriber_id,
+        SubscriberAction::Delete,
+    )
+    .await
+}
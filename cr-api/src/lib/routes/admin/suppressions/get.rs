@@ -0,0 +1,58 @@
+// src/routes/admin/suppressions/get.rs
+
+// dependencies
+use crate::domain::{Suppression, SuppressionsTemplate};
+use crate::errors::{e500, ResponseError};
+use crate::state::AppState;
+use anyhow::Context;
+use axum::extract::State;
+use axum_flash::IncomingFlashes;
+use sqlx::PgPool;
+use std::fmt::Write;
+
+// function which retrieves the whole suppression list, most recent first
+#[tracing::instrument(name = "Get suppressions", skip(pool))]
+async fn get_suppressions(pool: &PgPool) -> Result<Vec<Suppression>, anyhow::Error> {
+    let suppressions = sqlx::query!(
+        r#"
+        SELECT email, reason, source, created_at
+        FROM suppressions
+        ORDER BY created_at DESC, email
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to perform a query to retrieve the suppressions.")?
+    .into_iter()
+    .map(|r| Suppression {
+        email: r.email,
+        reason: r.reason,
+        source: r.source,
+        created_at: r.created_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+    })
+    .collect();
+    Ok(suppressions)
+}
+
+// handler to render the suppression list, together with the form to add to it
+#[tracing::instrument(name = "Suppressions", skip(flashes, app_state))]
+pub async fn suppressions(
+    flashes: IncomingFlashes,
+    State(app_state): State<AppState>,
+) -> Result<(IncomingFlashes, SuppressionsTemplate), ResponseError> {
+    // process any incoming flash messages
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    let suppressions = get_suppressions(&app_state.db_pool).await.map_err(e500)?;
+
+    // render the suppression list from its associated Askama template
+    let suppressions_template = SuppressionsTemplate {
+        flash_msg,
+        suppressions,
+    };
+
+    Ok((flashes, suppressions_template))
+}
@@ -0,0 +1,7 @@
+// src/lib/routes/admin/suppressions/mod.rs
+
+mod get;
+mod post;
+
+pub use get::suppressions;
+pub use post::{add_suppression, remove_suppression};
@@ -0,0 +1,114 @@
+// src/routes/admin/suppressions/post.rs
+
+// dependencies
+use crate::authentication::UserId;
+use crate::domain::SubscriberEmail;
+use crate::errors::{e500, ResponseError};
+use crate::state::AppState;
+use crate::suppressions::{suppress, SuppressionSource};
+use anyhow::Context;
+use axum::{
+    extract::{Form, State},
+    response::{IntoResponse, Redirect},
+    Extension,
+};
+use axum_flash::Flash;
+use serde::Deserialize;
+use sqlx::PgPool;
+
+// a struct to represent the form data received from the add suppression form
+#[derive(Debug, Deserialize)]
+pub struct AddSuppressionData {
+    email: String,
+    reason: String,
+}
+
+// a struct to represent the form data received from the remove suppression button
+#[derive(Debug, Deserialize)]
+pub struct RemoveSuppressionData {
+    email: String,
+}
+
+// function which takes an address off the suppression list, returns false if it wasn't on it
+#[tracing::instrument(skip(pool))]
+async fn delete_suppression(pool: &PgPool, email: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(r#"DELETE FROM suppressions WHERE email = lower($1)"#, email)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// add suppression handler
+#[tracing::instrument(
+name = "Add a suppression",
+skip(flash, suppression_data, app_state, user_id),
+fields(user_id=%*user_id)
+)]
+pub async fn add_suppression(
+    Extension(user_id): Extension<UserId>,
+    flash: Flash,
+    State(app_state): State<AppState>,
+    suppression_data: Form<AddSuppressionData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let AddSuppressionData { email, reason } = suppression_data.0;
+    let email = match SubscriberEmail::parse(email) {
+        Ok(email) => email,
+        Err(e) => {
+            let flash = flash.error(e);
+            return Ok((flash, Redirect::to("/admin/suppressions")).into_response());
+        }
+    };
+    let reason = match reason.trim() {
+        "" => "Added by an admin",
+        reason => reason,
+    };
+
+    let mut transaction = app_state
+        .db_pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool.")
+        .map_err(e500)?;
+    suppress(
+        &mut transaction,
+        email.as_ref(),
+        reason,
+        SuppressionSource::Admin,
+    )
+    .await
+    .context("Failed to add the address to the suppression list.")
+    .map_err(e500)?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the SQL transaction to add a suppression.")
+        .map_err(e500)?;
+
+    let flash = flash.info("The address has been added to the suppression list.");
+    Ok((flash, Redirect::to("/admin/suppressions")).into_response())
+}
+
+// remove suppression handler
+#[tracing::instrument(
+name = "Remove a suppression",
+skip(flash, suppression_data, app_state, user_id),
+fields(user_id=%*user_id)
+)]
+pub async fn remove_suppression(
+    Extension(user_id): Extension<UserId>,
+    flash: Flash,
+    State(app_state): State<AppState>,
+    suppression_data: Form<RemoveSuppressionData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let removed = delete_suppression(&app_state.db_pool, &suppression_data.email)
+        .await
+        .context("Failed to remove the address from the suppression list.")
+        .map_err(e500)?;
+
+    let flash = if removed {
+        flash.info("The address has been removed from the suppression list.")
+    } else {
+        flash.error("The address isn't on the suppression list.")
+    };
+    Ok((flash, Redirect::to("/admin/suppressions")).into_response())
+}
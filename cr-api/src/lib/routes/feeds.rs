@@ -0,0 +1,182 @@
+// src/lib/routes/feeds.rs
+
+// dependencies
+use crate::domain::{AtomFeedTemplate, FeedEntry, RssFeedTemplate, Template};
+use crate::errors::{e500, ResponseError};
+use crate::routes::public_html_content;
+use crate::state::AppState;
+use anyhow::Context;
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+// the number of most recent issues included in the feeds
+const FEED_LENGTH: i64 = 20;
+
+// a struct to represent a published issue, as included in the feeds
+struct FeedIssue {
+    slug: String,
+    title: String,
+    html_content: String,
+    published_at: DateTime<Utc>,
+}
+
+// function which retrieves the most recent published issues, newest first
+#[tracing::instrument(name = "Get feed issues", skip(pool))]
+async fn get_feed_issues(pool: &PgPool) -> Result<Vec<FeedIssue>, anyhow::Error> {
+    let issues = sqlx::query_as!(
+        FeedIssue,
+        r#"
+        SELECT
+            slug as "slug!",
+            title,
+            html_content,
+            published_at as "published_at!"
+        FROM newsletter_issues
+        WHERE status = 'published' AND slug IS NOT NULL
+        ORDER BY published_at DESC, newsletter_issue_id
+        LIMIT $1
+        "#,
+        FEED_LENGTH,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to perform a query to retrieve the feed issues.")?;
+    Ok(issues)
+}
+
+// function to turn the feed issues into entries, with their permalinks and the placeholders filled in
+fn feed_entries(
+    app_state: &AppState,
+    issues: &[FeedIssue],
+    format_date: fn(&DateTime<Utc>) -> String,
+) -> Vec<FeedEntry> {
+    issues
+        .iter()
+        .map(|issue| FeedEntry {
+            title: issue.title.clone(),
+            link: format!("{}/issues/{}", app_state.bs_url.0, issue.slug),
+            published_at: format_date(&issue.published_at),
+            html_content: public_html_content(&app_state.bs_url, &issue.html_content),
+        })
+        .collect()
+}
+
+// function to check whether the copy of the feed held by the client is still current; the ETag takes
+// precedence, the modification date is only looked at when the client didn't send one
+fn is_not_modified(headers: &HeaderMap, etag: &str, last_modified: Option<DateTime<Utc>>) -> bool {
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+        return if_none_match.to_str().is_ok_and(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+        });
+    }
+    let if_modified_since = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok());
+    match (if_modified_since, last_modified) {
+        (Some(since), Some(last_modified)) => last_modified.timestamp() <= since.timestamp(),
+        _ => false,
+    }
+}
+
+// function to build the response for a rendered feed, with the caching headers; the ETag is a hash of
+// the feed itself, so it changes whenever an issue is published or edited
+fn feed_response(
+    headers: &HeaderMap,
+    content_type: &'static str,
+    body: String,
+    last_modified: Option<DateTime<Utc>>,
+) -> Response {
+    let etag = format!(
+        "\"{}\"",
+        hex::encode(&Sha256::digest(body.as_bytes())[..16])
+    );
+    let mut response = if is_not_modified(headers, &etag, last_modified) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        (
+            [(header::CONTENT_TYPE, HeaderValue::from_static(content_type))],
+            body,
+        )
+            .into_response()
+    };
+    let response_headers = response.headers_mut();
+    response_headers.insert(
+        header::ETAG,
+        HeaderValue::from_str(&etag).expect("A hex encoded ETag is a valid header value"),
+    );
+    if let Some(last_modified) = last_modified {
+        let last_modified = last_modified
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string();
+        response_headers.insert(
+            header::LAST_MODIFIED,
+            HeaderValue::from_str(&last_modified).expect("An HTTP date is a valid header value"),
+        );
+    }
+    response
+}
+
+// RSS feed handler
+#[tracing::instrument(name = "RSS feed", skip(app_state, headers))]
+pub async fn rss_feed(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ResponseError> {
+    let issues = get_feed_issues(&app_state.db_pool).await.map_err(e500)?;
+    let last_modified = issues.first().map(|issue| issue.published_at);
+
+    // render the feed from its associated Askama template, RSS dates follow RFC 2822
+    let rss_date = |date: &DateTime<Utc>| date.to_rfc2822();
+    let body = RssFeedTemplate {
+        base_url: app_state.bs_url.0.clone(),
+        last_build_date: last_modified.as_ref().map(rss_date),
+        entries: feed_entries(&app_state, &issues, rss_date),
+    }
+    .render()
+    .map_err(e500)?;
+
+    Ok(feed_response(
+        &headers,
+        "application/rss+xml; charset=utf-8",
+        body,
+        last_modified,
+    ))
+}
+
+// Atom feed handler
+#[tracing::instrument(name = "Atom feed", skip(app_state, headers))]
+pub async fn atom_feed(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ResponseError> {
+    let issues = get_feed_issues(&app_state.db_pool).await.map_err(e500)?;
+    let last_modified = issues.first().map(|issue| issue.published_at);
+
+    // render the feed from its associated Askama template, Atom dates follow RFC 3339; a feed without
+    // any issue yet still needs an update time
+    let atom_date = |date: &DateTime<Utc>| date.to_rfc3339();
+    let body = AtomFeedTemplate {
+        base_url: app_state.bs_url.0.clone(),
+        updated: atom_date(&last_modified.unwrap_or(DateTime::UNIX_EPOCH)),
+        entries: feed_entries(&app_state, &issues, atom_date),
+    }
+    .render()
+    .map_err(e500)?;
+
+    Ok(feed_response(
+        &headers,
+        "application/atom+xml; charset=utf-8",
+        body,
+        last_modified,
+    ))
+}
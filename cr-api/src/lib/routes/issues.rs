@@ -0,0 +1,163 @@
+// src/lib/routes/issues.rs
+
+// dependencies
+use crate::domain::{ArchiveTemplate, ArchivedIssue, ArchivedIssueTemplate, Personalization};
+use crate::errors::{e400, e404, e500, ResponseError};
+use crate::state::{AppState, ApplicationBaseUrl};
+use anyhow::Context;
+use axum::extract::{Path, Query, State};
+use axum_flash::IncomingFlashes;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::fmt::Write;
+
+// the number of issues listed on each page of the archive
+const ISSUES_PER_PAGE: i64 = 10;
+
+// struct to represent the query parameters of the archive, pages start at 1
+#[derive(Debug, Deserialize)]
+pub struct ArchiveParameters {
+    page: Option<i64>,
+}
+
+// a struct to represent a published issue, as shown on its archive page
+struct PublishedIssue {
+    title: String,
+    html_content: String,
+    published_at: DateTime<Utc>,
+}
+
+// function to format the publication time as the date shown in the archive
+fn publication_date(published_at: &DateTime<Utc>) -> String {
+    published_at.format("%Y-%m-%d").to_string()
+}
+
+// function to fill in the placeholders of a published issue for the public, rather than for a subscriber;
+// the archive and the feeds are open to anyone, so personal details are never shown there
+pub fn public_html_content(base_url: &ApplicationBaseUrl, html_content: &str) -> String {
+    let home_page = format!("{}/", base_url.0);
+    Personalization {
+        name: "reader",
+        email: "your email address",
+        unsubscribe_url: &home_page,
+    }
+    .expand_html(html_content)
+}
+
+// function which retrieves a page of published issues, newest first, starting at the given offset, along with
+// whether an older page exists
+#[tracing::instrument(name = "Get archived issues", skip(pool))]
+async fn get_archived_issues(
+    pool: &PgPool,
+    offset: i64,
+) -> Result<(Vec<ArchivedIssue>, bool), anyhow::Error> {
+    let mut issues: Vec<ArchivedIssue> = sqlx::query!(
+        r#"
+        SELECT slug as "slug!", title, published_at as "published_at!"
+        FROM newsletter_issues
+        WHERE status = 'published' AND slug IS NOT NULL
+        ORDER BY published_at DESC, newsletter_issue_id
+        LIMIT $1 OFFSET $2
+        "#,
+        ISSUES_PER_PAGE + 1,
+        offset,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to perform a query to retrieve the archived issues.")?
+    .into_iter()
+    .map(|r| ArchivedIssue {
+        slug: r.slug,
+        title: r.title,
+        published_at: publication_date(&r.published_at),
+    })
+    .collect();
+    let has_older = issues.len() as i64 > ISSUES_PER_PAGE;
+    issues.truncate(ISSUES_PER_PAGE as usize);
+    Ok((issues, has_older))
+}
+
+// function which retrieves a published issue by its slug
+#[tracing::instrument(name = "Get archived issue", skip(pool))]
+async fn get_published_issue(
+    pool: &PgPool,
+    slug: &str,
+) -> Result<Option<PublishedIssue>, anyhow::Error> {
+    let issue = sqlx::query_as!(
+        PublishedIssue,
+        r#"
+        SELECT title, html_content, published_at as "published_at!"
+        FROM newsletter_issues
+        WHERE slug = $1 AND status = 'published'
+        "#,
+        slug
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to perform a query to retrieve the archived issue.")?;
+    Ok(issue)
+}
+
+// handler to render a page of the public archive of published issues
+#[tracing::instrument(name = "Archive", skip(flashes, app_state))]
+pub async fn archive(
+    flashes: IncomingFlashes,
+    State(app_state): State<AppState>,
+    parameters: Query<ArchiveParameters>,
+) -> Result<(IncomingFlashes, ArchiveTemplate), ResponseError> {
+    // process any incoming flash messages
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    // a page number too large to list is rejected, any page which can be listed leaves room for the next one
+    let page = parameters.page.unwrap_or(1).max(1);
+    let offset = (page - 1)
+        .checked_mul(ISSUES_PER_PAGE)
+        .ok_or_else(|| e400(anyhow::anyhow!("{} is not a valid page number.", page)))?;
+    let (issues, has_older) = get_archived_issues(&app_state.db_pool, offset)
+        .await
+        .map_err(e500)?;
+
+    // render the archive from its associated Askama template
+    let archive_template = ArchiveTemplate {
+        flash_msg,
+        issues,
+        newer_page: (page > 1).then_some(page - 1),
+        older_page: has_older.then_some(page + 1),
+    };
+
+    Ok((flashes, archive_template))
+}
+
+// handler to render a single published issue from the public archive
+#[tracing::instrument(name = "Archived issue", skip(flashes, app_state))]
+pub async fn archived_issue(
+    flashes: IncomingFlashes,
+    State(app_state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<(IncomingFlashes, ArchivedIssueTemplate), ResponseError> {
+    // process any incoming flash messages
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    let issue = get_published_issue(&app_state.db_pool, &slug)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| anyhow::anyhow!("There is no published issue with the provided slug."))
+        .map_err(e404)?;
+
+    // render the issue from its associated Askama template
+    let archived_issue_template = ArchivedIssueTemplate {
+        flash_msg,
+        title: issue.title,
+        published_at: publication_date(&issue.published_at),
+        html_content: public_html_content(&app_state.bs_url, &issue.html_content),
+    };
+
+    Ok((flashes, archived_issue_template))
+}
@@ -1,15 +1,25 @@
 //! src/lib/routes/mod.rs
 
 mod admin;
+pub mod feeds;
 pub mod health_check;
 mod home;
+pub mod issues;
 mod login;
+pub mod postmark_webhook;
 pub mod subscriptions;
 pub mod subscriptions_confirm;
+pub mod subscriptions_resend;
+pub mod subscriptions_unsubscribe;
 
 pub use admin::*;
+pub use feeds::*;
 pub use health_check::*;
 pub use home::*;
+pub use issues::*;
 pub use login::*;
+pub use postmark_webhook::*;
 pub use subscriptions::*;
 pub use subscriptions_confirm::*;
+pub use subscriptions_resend::*;
+pub use subscriptions_unsubscribe::*;
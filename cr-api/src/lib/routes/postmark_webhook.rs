@@ -0,0 +1,213 @@
+// src/routes/postmark_webhook.rs
+
+// dependencies
+use crate::errors::WebhookError;
+use crate::state::{AppState, PostmarkWebhookCredentials};
+use crate::suppressions::{suppress, SuppressionSource};
+use anyhow::Context;
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use base64::Engine;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use sqlx::{Executor, Postgres, Transaction};
+use tracing::Span;
+use uuid::Uuid;
+
+// the bounce types after which an address won't ever accept our email again
+const PERMANENT_BOUNCE_TYPES: [&str; 3] = ["HardBounce", "BadEmailAddress", "ManuallyDeactivated"];
+
+// a struct to represent the parts of a Postmark bounce or spam complaint payload we act upon
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PostmarkEvent {
+    record_type: String,
+    #[serde(rename = "ID")]
+    id: i64,
+    #[serde(rename = "Type", default)]
+    bounce_type: String,
+    email: String,
+}
+
+// an enum to represent the kinds of webhook events recorded in the subscriber_events table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubscriberEventType {
+    Bounce,
+    SpamComplaint,
+}
+
+impl SubscriberEventType {
+    // Postmark posts deliveries, opens and clicks to webhooks as well, those aren't recorded
+    fn from_record_type(record_type: &str) -> Option<Self> {
+        match record_type {
+            "Bounce" => Some(Self::Bounce),
+            "SpamComplaint" => Some(Self::SpamComplaint),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bounce => "bounce",
+            Self::SpamComplaint => "spam_complaint",
+        }
+    }
+
+    // the status the subscription moves to, soft bounces leave it as it is
+    fn subscription_status(&self, bounce_type: &str) -> Option<&'static str> {
+        match self {
+            Self::Bounce if PERMANENT_BOUNCE_TYPES.contains(&bounce_type) => Some("bounced"),
+            Self::Bounce => None,
+            Self::SpamComplaint => Some("complained"),
+        }
+    }
+}
+
+// function to extract basic auth credentials from the request headers
+fn basic_authentication(headers: &HeaderMap) -> Result<(String, Secret<String>), anyhow::Error> {
+    let header_value = headers
+        .get("Authorization")
+        .context("The 'Authorization' header was missing.")?
+        .to_str()
+        .context("The 'Authorization' header was not a valid UTF8 string.")?;
+    let base64encoded_segment = header_value
+        .strip_prefix("Basic ")
+        .context("The authorization scheme was not 'Basic'.")?;
+    let decoded_bytes = base64::engine::general_purpose::STANDARD
+        .decode(base64encoded_segment)
+        .context("Failed to base64-decode 'Basic' credentials.")?;
+    let decoded_credentials = String::from_utf8(decoded_bytes)
+        .context("The decoded credential string is not valid UTF8.")?;
+    let (username, password) = decoded_credentials
+        .split_once(':')
+        .context("A username and a password must be provided in 'Basic' auth.")?;
+    Ok((username.to_string(), Secret::new(password.to_string())))
+}
+
+// function to check the credentials against the configured ones, the password is compared in constant time
+fn verify_credentials(
+    (username, password): (String, Secret<String>),
+    expected: &PostmarkWebhookCredentials,
+) -> Result<(), anyhow::Error> {
+    let password = password.expose_secret().as_bytes();
+    let expected_password = expected.password.expose_secret().as_bytes();
+    let passwords_match = password.len() == expected_password.len()
+        && password
+            .iter()
+            .zip(expected_password)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0;
+    if username == expected.username && passwords_match {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Invalid webhook credentials."))
+    }
+}
+
+// function to record an event, returns false if Postmark already delivered it before
+#[tracing::instrument(skip(transaction, event, payload))]
+async fn store_subscriber_event(
+    transaction: &mut Transaction<'_, Postgres>,
+    event: &PostmarkEvent,
+    event_type: SubscriberEventType,
+    payload: &str,
+) -> Result<bool, sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO subscriber_events (
+            id,
+            subscriber_id,
+            email,
+            event_type,
+            detail,
+            provider_event_id,
+            payload,
+            received_at
+        )
+        VALUES ($1, (SELECT id FROM subscriptions WHERE lower(email) = lower($2) LIMIT 1), $2, $3, $4, $5, $6, now())
+        ON CONFLICT (event_type, provider_event_id) DO NOTHING
+        "#,
+        Uuid::new_v4(),
+        event.email,
+        event_type.as_str(),
+        event.bounce_type,
+        event.id,
+        payload,
+    );
+    let result = transaction.execute(query).await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// function to move the subscription of the given address to a new status
+#[tracing::instrument(skip(transaction))]
+async fn update_subscription_status(
+    transaction: &mut Transaction<'_, Postgres>,
+    email: &str,
+    status: &str,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"UPDATE subscriptions SET status = $2 WHERE lower(email) = lower($1)"#,
+        email,
+        status,
+    );
+    transaction.execute(query).await?;
+    Ok(())
+}
+
+// Postmark bounce and spam complaint webhook handler
+#[tracing::instrument(
+    name = "Receive a Postmark webhook",
+    skip(app_state, headers, body),
+    fields(record_type=tracing::field::Empty, subscriber_email=tracing::field::Empty)
+)]
+pub async fn postmark_webhook(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<StatusCode, WebhookError> {
+    let credentials = basic_authentication(&headers).map_err(WebhookError::AuthError)?;
+    verify_credentials(credentials, &app_state.webhook_credentials)
+        .map_err(WebhookError::AuthError)?;
+
+    let event: PostmarkEvent = serde_json::from_str(&body)
+        .context("Failed to parse the webhook payload.")
+        .map_err(WebhookError::InvalidPayload)?;
+    Span::current()
+        .record("record_type", tracing::field::display(&event.record_type))
+        .record("subscriber_email", tracing::field::display(&event.email));
+    let Some(event_type) = SubscriberEventType::from_record_type(&event.record_type) else {
+        return Ok(StatusCode::OK);
+    };
+
+    let mut transaction = app_state
+        .db_pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool.")?;
+    let recorded = store_subscriber_event(&mut transaction, &event, event_type, &body)
+        .await
+        .context("Failed to store the subscriber event.")?;
+    if let Some(status) = event_type.subscription_status(&event.bounce_type) {
+        if recorded {
+            update_subscription_status(&mut transaction, &event.email, status)
+                .await
+                .context("Failed to update the subscription status.")?;
+            suppress(
+                &mut transaction,
+                &event.email,
+                status,
+                SuppressionSource::Postmark,
+            )
+            .await
+            .context("Failed to add the address to the suppression list.")?;
+        }
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the SQL transaction to store a subscriber event.")?;
+
+    Ok(StatusCode::OK)
+}
@@ -1,14 +1,23 @@
 // src/lib/routes/subscribe.rs
 
 // dependencies
-use crate::domain::PendingConfirmationTemplate;
+use crate::domain::{
+    AlreadySubscribedEmailHtml, AlreadySubscribedEmailText, ConfirmationEmailHtml,
+    ConfirmationEmailText, EmailBody, PendingConfirmationTemplate,
+};
 use crate::domain::{NewSubscriber, SubscriberEmail, SubscriberName};
-use crate::email_client::EmailClient;
+use crate::email_outbox_worker::enqueue_email;
 use crate::errors::{StoreTokenError, SubscribeError};
-use crate::state::AppState;
+use crate::issue_delivery_worker::unsubscribe_link;
+use crate::routes::subscriptions_resend::{
+    client_ip, is_throttled, lock_resend_limits, record_resend_request,
+};
+use crate::state::{AppState, ApplicationBaseUrl, HmacSecret};
+use crate::suppressions::is_suppressed;
 use anyhow::Context;
 use axum::{
-    extract::{Form, State},
+    extract::{ConnectInfo, Form, State},
+    http::HeaderMap,
     response::Result,
 };
 use axum_flash::IncomingFlashes;
@@ -18,6 +27,7 @@ use rand::{thread_rng, Rng};
 use serde::Deserialize;
 use sqlx::{Executor, Postgres, Transaction};
 use std::fmt::Write;
+use std::net::SocketAddr;
 use uuid::Uuid;
 
 // data structure to model the incoming form data from the subscribe handler
@@ -27,6 +37,12 @@ pub struct SubscriptionData {
     name: String,
 }
 
+// a struct to represent the subscriber stored under the address being subscribed
+struct StoredSubscriber {
+    id: Uuid,
+    status: String,
+}
+
 // implement the TryFrom conversion trait for the incoming form data, to convert it into our domain data type
 impl TryFrom<Form<SubscriptionData>> for NewSubscriber {
     type Error = String;
@@ -39,7 +55,7 @@ impl TryFrom<Form<SubscriptionData>> for NewSubscriber {
 }
 
 // function to generate a random 25-characters-long case-sensitive subscription token.
-fn generate_subscription_token() -> String {
+pub fn generate_subscription_token() -> String {
     let mut rng = thread_rng();
     std::iter::repeat_with(|| rng.sample(Alphanumeric))
         .map(char::from)
@@ -67,7 +83,8 @@ VALUES ($1, $2)"#,
     Ok(())
 }
 
-// function to insert a new subscriber into the subscriptions database
+// function to insert a new subscriber into the subscriptions database; an address stored in the meantime, by a
+// concurrent signup, is left alone, in which case no id is returned
 #[tracing::instrument(
     name = "Saving new subscriber details in the database",
     skip(new_subscriber, transaction)
@@ -75,110 +92,262 @@ VALUES ($1, $2)"#,
 pub async fn insert_subscriber(
     transaction: &mut Transaction<'_, Postgres>,
     new_subscriber: &NewSubscriber,
-) -> Result<Uuid, sqlx::Error> {
-    let subscriber_id = Uuid::new_v4();
-    let query = sqlx::query!(
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let subscriber_id = sqlx::query!(
         r#"
         INSERT INTO subscriptions (id, email, name, subscribed_at, status) VALUES ($1, $2, $3, $4, 'pending_confirmation')
+        ON CONFLICT (email) DO NOTHING
+        RETURNING id
         "#,
-        subscriber_id,
+        Uuid::new_v4(),
         new_subscriber.email.as_ref(),
         new_subscriber.name.as_ref(),
         Utc::now()
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .map(|r| r.id);
+    Ok(subscriber_id)
+}
+
+// function to look up the subscriber stored under an address, locking the row for the rest of the transaction
+#[tracing::instrument(
+    name = "Looking up an existing subscriber in the database",
+    skip(transaction, email)
+)]
+async fn get_existing_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    email: &SubscriberEmail,
+) -> Result<Option<StoredSubscriber>, sqlx::Error> {
+    sqlx::query_as!(
+        StoredSubscriber,
+        r#"SELECT id, status FROM subscriptions WHERE email = $1 FOR UPDATE"#,
+        email.as_ref(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+}
+
+// function to put a subscriber who left, or was taken off the suppression list, back to awaiting confirmation
+#[tracing::instrument(
+    name = "Resetting a subscriber to pending confirmation",
+    skip(transaction)
+)]
+async fn mark_subscriber_as_pending(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"UPDATE subscriptions SET status = 'pending_confirmation' WHERE id = $1"#,
+        subscriber_id,
     );
     transaction.execute(query).await?;
-    Ok(subscriber_id)
+    Ok(())
 }
 
-// function which sends out a confirmation email
+// function which writes a confirmation email to the outbox, to be sent once the transaction commits
 #[tracing::instrument(
-    name = "Sending a confirmation email to a new subscriber",
-    skip(email_client, new_subscriber, base_url)
+    name = "Queueing a confirmation email to a new subscriber",
+    skip(transaction, recipient, base_url, subscription_token)
 )]
-pub async fn send_confirmation_email(
-    email_client: &EmailClient,
-    new_subscriber: NewSubscriber,
+pub async fn enqueue_confirmation_email(
+    transaction: &mut Transaction<'_, Postgres>,
+    recipient: &SubscriberEmail,
     base_url: &str,
     subscription_token: &str,
-) -> Result<(), reqwest::Error> {
+) -> Result<(), anyhow::Error> {
     let confirmation_link = format!(
         "{}/subscriptions/confirm?subscription_token={}",
         base_url, subscription_token
     );
+    let body = EmailBody::render(
+        ConfirmationEmailHtml {
+            confirmation_link: &confirmation_link,
+        },
+        ConfirmationEmailText {
+            confirmation_link: &confirmation_link,
+        },
+    )?;
 
-    let plain_body = format!(
-        "Welcome to the Crusty Rustacean newsletter!\nVisit {} to confirm your subscription.",
-        confirmation_link
-    );
+    enqueue_email(
+        transaction,
+        recipient,
+        "Please confirm your subscription",
+        &body.html,
+        &body.text,
+    )
+    .await?;
+    Ok(())
+}
 
-    let html_body = &format!(
-        "<h1>Crusty Rustacean - The Newsletter</h1>
-        <h2>A source for all things Rust</h2>
-        <h3>Mostly once a month...mostly...</h3>
-        <p>Welcome!</p>
-        <p>Click <a href=\"{}\">here</a> to confirm your subscription.</p>
-        <p>After clicking, a confirmation page will come up to confirm.",
-        confirmation_link
-    );
+// function which writes a notice to the outbox, letting a confirmed subscriber know that signing up again wasn't needed
+#[tracing::instrument(
+    name = "Queueing an already subscribed notice to a confirmed subscriber",
+    skip_all
+)]
+pub async fn enqueue_already_subscribed_email(
+    transaction: &mut Transaction<'_, Postgres>,
+    recipient: &SubscriberEmail,
+    base_url: &ApplicationBaseUrl,
+    hmac_secret: &HmacSecret,
+    subscriber_id: Uuid,
+) -> Result<(), anyhow::Error> {
+    let unsubscribe_link = unsubscribe_link(base_url, hmac_secret, subscriber_id);
+    let body = EmailBody::render(
+        AlreadySubscribedEmailHtml {
+            unsubscribe_link: &unsubscribe_link,
+        },
+        AlreadySubscribedEmailText {
+            unsubscribe_link: &unsubscribe_link,
+        },
+    )?;
 
-    email_client
-        .send_email(&new_subscriber.email, "Welcome!", html_body, &plain_body)
+    enqueue_email(
+        transaction,
+        recipient,
+        "You're already subscribed",
+        &body.html,
+        &body.text,
+    )
+    .await?;
+    Ok(())
+}
+
+// function to look up the subscriber stored under an address, storing a new one awaiting confirmation if there
+// is none; the boolean tells whether the subscriber was already there
+async fn get_or_insert_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    new_subscriber: &NewSubscriber,
+) -> Result<(StoredSubscriber, bool), anyhow::Error> {
+    if let Some(subscriber) = get_existing_subscriber(transaction, &new_subscriber.email)
         .await
+        .context("Failed to look up an existing subscriber in the database.")?
+    {
+        return Ok((subscriber, true));
+    }
+    match insert_subscriber(transaction, new_subscriber)
+        .await
+        .context("Failed to insert a new subscriber in the database.")?
+    {
+        Some(id) => Ok((
+            StoredSubscriber {
+                id,
+                status: "pending_confirmation".to_string(),
+            },
+            false,
+        )),
+        None => {
+            let subscriber = get_existing_subscriber(transaction, &new_subscriber.email)
+                .await
+                .context("Failed to look up an existing subscriber in the database.")?
+                .context("The subscriber stored by a concurrent signup couldn't be found.")?;
+            Ok((subscriber, true))
+        }
+    }
 }
 
 // subscribe handler function
 #[tracing::instrument(
     name = "Adding a new subscriber",
-    skip(subscription_data, app_state),
+    skip(subscription_data, app_state, headers),
     fields(
         subscriber_email = %subscription_data.email,
-        subscriber_name = %subscription_data.name
+        subscriber_name = %subscription_data.name,
+        client_ip = tracing::field::Empty
     )
 )]
 pub async fn subscribe(
     flashes: IncomingFlashes,
     State(app_state): State<AppState>,
+    ConnectInfo(peer_address): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     subscription_data: Form<SubscriptionData>,
 ) -> Result<(IncomingFlashes, PendingConfirmationTemplate), SubscribeError> {
-    let new_subscriber = subscription_data
+    let new_subscriber: NewSubscriber = subscription_data
         .try_into()
         .map_err(SubscribeError::ValidationError)?;
 
+    // process any incoming flash messages and convert them to a string for rendering
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    // a suppressed address gets the same response as everyone else, but is neither stored nor emailed
+    if is_suppressed(&app_state.db_pool, new_subscriber.email.as_ref())
+        .await
+        .context("Failed to check the suppression list.")?
+    {
+        tracing::info!("Ignoring a subscription request from a suppressed address.");
+        return Ok((flashes, PendingConfirmationTemplate { flash_msg }));
+    }
+
     let mut transaction = app_state
         .db_pool
         .begin()
         .await
         .context("Failed to acquire a Postgres connection from the pool.")?;
 
-    let subscriber_id = insert_subscriber(&mut transaction, &new_subscriber)
+    // signing up again is answered by email only, the response is the same whatever state the address is in;
+    // emails go through the outbox, so they are only sent if the transaction commits
+    // the emails sent for repeat signups count towards the same limits as resend requests, so that signing up
+    // over and over can't flood an inbox, an address over the limit is silently not emailed
+    let client_ip = client_ip(&headers, peer_address, &app_state.trusted_proxy_hops);
+    tracing::Span::current().record("client_ip", tracing::field::display(&client_ip));
+    let email = new_subscriber.email.as_ref();
+    lock_resend_limits(&mut transaction, email, &client_ip)
         .await
-        .context("Failed to insert a new subscriber in the database.")?;
-
-    let subscription_token = generate_subscription_token();
-    store_token(&mut transaction, subscriber_id, &subscription_token)
+        .context("Failed to lock the resend request limits.")?;
+    let (subscriber, is_repeat) =
+        get_or_insert_subscriber(&mut transaction, &new_subscriber).await?;
+    if is_repeat {
+        if is_throttled(&mut transaction, email, &client_ip)
+            .await
+            .context("Failed to check the resend request limits.")?
+        {
+            tracing::warn!("Too many repeat signups were made for this address.");
+            return Ok((flashes, PendingConfirmationTemplate { flash_msg }));
+        }
+        record_resend_request(&mut transaction, email, &client_ip)
+            .await
+            .context("Failed to record the repeat signup.")?;
+    }
+    if subscriber.status == "confirmed" {
+        enqueue_already_subscribed_email(
+            &mut transaction,
+            &new_subscriber.email,
+            &app_state.bs_url,
+            &app_state.hmac_secret,
+            subscriber.id,
+        )
+        .await
+        .context("Failed to queue an already subscribed notice.")?;
+    } else {
+        if subscriber.status != "pending_confirmation" {
+            mark_subscriber_as_pending(&mut transaction, subscriber.id)
+                .await
+                .context("Failed to reset the subscriber to pending confirmation.")?;
+        }
+        // a fresh token, so that a subscriber who lost their confirmation email gets a new link
+        let subscription_token = generate_subscription_token();
+        store_token(&mut transaction, subscriber.id, &subscription_token)
+            .await
+            .context("Failed to store the confirmation token for a new subscriber.")?;
+        enqueue_confirmation_email(
+            &mut transaction,
+            &new_subscriber.email,
+            &app_state.bs_url.0,
+            &subscription_token,
+        )
         .await
-        .context("Failed to store the confirmation token for a new subscriber.")?;
+        .context("Failed to queue a confirmation email.")?;
+    }
 
     transaction
         .commit()
         .await
         .context("Failed to commit SQL transaction to store a new subscriber.")?;
 
-    send_confirmation_email(
-        &app_state.em_client,
-        new_subscriber,
-        &app_state.bs_url.0,
-        &subscription_token,
-    )
-    .await
-    .context("Failed to send a confirmation email.")?;
-
-    // process any incoming flash messages and convert them to a string for rendering
-    let mut flash_msg = String::new();
-    for (level, text) in flashes.iter() {
-        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
-    }
-
     // render the change password form, given that there is a valid user session, display any error message
     // TODO: make sure errors are rendered properly, as it stands now, this page will render regardless of any errors
     let pending_confirmation_template = PendingConfirmationTemplate { flash_msg };
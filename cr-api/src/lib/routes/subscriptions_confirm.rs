@@ -2,48 +2,117 @@
 
 // dependencies
 
-use crate::state::AppState;
-use crate::{domain::SubscriptionConfirmationTemplate, errors::ConfirmationError};
+use crate::domain::{
+    EmailBody, SubscriberEmail, SubscriptionConfirmationTemplate, WelcomeEmailHtml,
+    WelcomeEmailText,
+};
+use crate::email_outbox_worker::enqueue_email;
+use crate::errors::ConfirmationError;
+use crate::issue_delivery_worker::unsubscribe_link;
+use crate::state::{AppState, ApplicationBaseUrl, HmacSecret};
 use anyhow::Context;
 use axum::extract::{Query, State};
 use axum_flash::IncomingFlashes;
+use chrono::{Duration, Utc};
 use serde::Deserialize;
-use sqlx::PgPool;
+use sqlx::{Executor, Postgres, Transaction};
 use std::fmt::Write;
 use uuid::Uuid;
 
+// how long a confirmation link stays valid after it was sent
+const SUBSCRIPTION_TOKEN_TTL_HOURS: i64 = 48;
+
 // struct to represent the query parameters, which includes a subscription token
 #[derive(Debug, Deserialize)]
 pub struct Parameters {
     subscription_token: String,
 }
 
-// function which confirms the subscriber in the database
-#[tracing::instrument(name = "Mark subscriber as confirmed", skip(subscriber_id, pool))]
-pub async fn confirm_subscriber(pool: &PgPool, subscriber_id: Uuid) -> Result<(), sqlx::Error> {
-    sqlx::query!(
-        r#"UPDATE subscriptions SET status = 'confirmed' WHERE id = $1"#,
+// function which confirms the subscriber in the database, returns their email address; only a subscriber still
+// awaiting confirmation is confirmed, so that a link sent before a bounce, complaint or unsubscribe can't bring
+// the subscription back, nothing is returned in that case
+#[tracing::instrument(
+    name = "Mark subscriber as confirmed",
+    skip(subscriber_id, transaction)
+)]
+pub async fn confirm_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+) -> Result<Option<String>, sqlx::Error> {
+    let subscriber = sqlx::query!(
+        r#"
+        UPDATE subscriptions SET status = 'confirmed'
+        WHERE id = $1 AND status = 'pending_confirmation'
+        RETURNING email
+        "#,
         subscriber_id,
     )
-    .execute(pool)
+    .fetch_optional(&mut **transaction)
     .await?;
+    Ok(subscriber.map(|r| r.email))
+}
+
+// function which writes a welcome email to the outbox, to be sent once the subscription is confirmed
+#[tracing::instrument(name = "Queueing a welcome email to a new subscriber", skip_all)]
+async fn enqueue_welcome_email(
+    transaction: &mut Transaction<'_, Postgres>,
+    recipient: &SubscriberEmail,
+    base_url: &ApplicationBaseUrl,
+    hmac_secret: &HmacSecret,
+    subscriber_id: Uuid,
+) -> Result<(), anyhow::Error> {
+    let unsubscribe_link = unsubscribe_link(base_url, hmac_secret, subscriber_id);
+    let body = EmailBody::render(
+        WelcomeEmailHtml {
+            unsubscribe_link: &unsubscribe_link,
+        },
+        WelcomeEmailText {
+            unsubscribe_link: &unsubscribe_link,
+        },
+    )?;
+
+    enqueue_email(transaction, recipient, "Welcome!", &body.html, &body.text).await?;
+    Ok(())
+}
+
+// function which marks a subscription token as used, so that it can't confirm anything again
+#[tracing::instrument(name = "Consume subscription token", skip_all)]
+async fn consume_token(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscription_token: &str,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"UPDATE subscription_tokens SET consumed_at = now() WHERE subscription_token = $1"#,
+        subscription_token,
+    );
+    transaction.execute(query).await?;
     Ok(())
 }
 
-// function which retrieves a subscriber id from an incoming subscription token
-#[tracing::instrument(name = "Get subscriber_id from token", skip(subscription_token, pool))]
+// function which retrieves a subscriber id from an incoming subscription token, as long as the token
+// is neither expired nor used; the token is locked for the rest of the transaction
+#[tracing::instrument(
+    name = "Get subscriber_id from token",
+    skip(subscription_token, transaction)
+)]
 pub async fn get_subscriber_id_from_token(
-    pool: &PgPool,
+    transaction: &mut Transaction<'_, Postgres>,
     subscription_token: &str,
-) -> Result<Option<Uuid>, sqlx::Error> {
-    let result = sqlx::query!(
-        "SELECT subscriber_id FROM subscription_tokens \
-WHERE subscription_token = $1",
+) -> Result<Uuid, ConfirmationError> {
+    let token = sqlx::query!(
+        "SELECT subscriber_id, created_at, consumed_at FROM subscription_tokens \
+WHERE subscription_token = $1 FOR UPDATE",
         subscription_token,
     )
-    .fetch_optional(pool)
-    .await?;
-    Ok(result.map(|r| r.subscriber_id))
+    .fetch_optional(&mut **transaction)
+    .await
+    .context("Failed to retrieve the subscriber id associated with the provided token.")?
+    .ok_or(ConfirmationError::UnknownToken)?;
+    let expired = token.created_at + Duration::hours(SUBSCRIPTION_TOKEN_TTL_HOURS) < Utc::now();
+    if expired || token.consumed_at.is_some() {
+        return Err(ConfirmationError::ExpiredToken);
+    }
+    Ok(token.subscriber_id)
 }
 
 // confirm handler
@@ -53,14 +122,36 @@ pub async fn confirm(
     flashes: IncomingFlashes,
     parameters: Query<Parameters>,
 ) -> Result<(IncomingFlashes, SubscriptionConfirmationTemplate), ConfirmationError> {
+    let mut transaction = app_state
+        .db_pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool.")?;
     let subscriber_id =
-        get_subscriber_id_from_token(&app_state.db_pool, &parameters.subscription_token)
-            .await
-            .context("Failed to retrieve the subscriber id associated with the provided token.")?
-            .ok_or(ConfirmationError::UnknownToken)?;
-    confirm_subscriber(&app_state.db_pool, subscriber_id)
+        get_subscriber_id_from_token(&mut transaction, &parameters.subscription_token).await?;
+    consume_token(&mut transaction, &parameters.subscription_token)
+        .await
+        .context("Failed to mark the subscription token as used.")?;
+    let subscriber_email = confirm_subscriber(&mut transaction, subscriber_id)
+        .await
+        .context("Failed to update the subscriber status to 'confirmed'.")?
+        .ok_or(ConfirmationError::ExpiredToken)?;
+    // a stored address which no longer parses simply doesn't get the welcome email
+    if let Ok(subscriber_email) = SubscriberEmail::parse(subscriber_email) {
+        enqueue_welcome_email(
+            &mut transaction,
+            &subscriber_email,
+            &app_state.bs_url,
+            &app_state.hmac_secret,
+            subscriber_id,
+        )
+        .await
+        .context("Failed to queue a welcome email.")?;
+    }
+    transaction
+        .commit()
         .await
-        .context("Failed to update the subscriber status to 'confirmed'.")?;
+        .context("Failed to commit SQL transaction to confirm a subscriber.")?;
 
     // process any incoming flash messages
     let mut flash_msg = String::new();
@@ -0,0 +1,235 @@
+// src/lib/routes/subscriptions_resend.rs
+
+// dependencies
+use crate::domain::{PendingConfirmationTemplate, ResendConfirmationTemplate, SubscriberEmail};
+use crate::errors::SubscribeError;
+use crate::routes::{enqueue_confirmation_email, generate_subscription_token, store_token};
+use crate::state::{AppState, TrustedProxyHops};
+use crate::suppressions::is_suppressed;
+use anyhow::Context;
+use axum::{
+    extract::{ConnectInfo, Form, State},
+    http::HeaderMap,
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_flash::{Flash, IncomingFlashes};
+use serde::Deserialize;
+use sqlx::{Executor, Postgres, Transaction};
+use std::fmt::Write;
+use std::net::SocketAddr;
+use uuid::Uuid;
+
+// the most confirmation emails a single address may be sent through this form within an hour
+const MAX_RESENDS_PER_EMAIL_PER_HOUR: i64 = 3;
+// the most resend requests a single client may make within an hour, whatever the addresses
+const MAX_RESENDS_PER_CLIENT_PER_HOUR: i64 = 10;
+
+// data structure to model the incoming form data from the resend confirmation form
+#[derive(Deserialize)]
+pub struct ResendData {
+    email: String,
+}
+
+// function to work out the address of the client; every trusted proxy in front of the app appends the address
+// it received the request from to the X-Forwarded-For header, so the entry added by the outermost one is taken,
+// counting from the right, as anything to its left is up to the client
+// the peer address is used when there are no trusted proxies, or the header doesn't have an entry for each
+pub(crate) fn client_ip(
+    headers: &HeaderMap,
+    peer_address: SocketAddr,
+    trusted_proxy_hops: &TrustedProxyHops,
+) -> String {
+    let hops = trusted_proxy_hops.0 as usize;
+    if hops == 0 {
+        return peer_address.ip().to_string();
+    }
+    headers
+        .get("X-Forwarded-For")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').nth(hops - 1))
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty())
+        .unwrap_or_else(|| peer_address.ip().to_string())
+}
+
+// function to serialise resend requests, and repeat signups, for the same address, or from the same client, until
+// the transaction ends, so that concurrent requests can't all pass the limits before any of them is recorded
+// the address is always locked before the client, so that two requests can't wait on each other
+#[tracing::instrument(name = "Lock the resend request limits", skip(transaction, email))]
+pub(crate) async fn lock_resend_limits(
+    transaction: &mut Transaction<'_, Postgres>,
+    email: &str,
+    client_ip: &str,
+) -> Result<(), sqlx::Error> {
+    transaction
+        .execute(sqlx::query!(
+            "SELECT FROM pg_advisory_xact_lock(1, hashtext(lower($1)))",
+            email
+        ))
+        .await?;
+    transaction
+        .execute(sqlx::query!(
+            "SELECT FROM pg_advisory_xact_lock(2, hashtext($1))",
+            client_ip
+        ))
+        .await?;
+    Ok(())
+}
+
+// function to check whether another resend request for the address, or from the client, is allowed
+#[tracing::instrument(name = "Check the resend request limits", skip(transaction, email))]
+pub(crate) async fn is_throttled(
+    transaction: &mut Transaction<'_, Postgres>,
+    email: &str,
+    client_ip: &str,
+) -> Result<bool, sqlx::Error> {
+    let recent = sqlx::query!(
+        r#"
+        SELECT
+            COUNT(*) FILTER (WHERE email = lower($1)) as "per_email!",
+            COUNT(*) FILTER (WHERE client_ip = $2) as "per_client!"
+        FROM confirmation_resend_requests
+        WHERE
+            requested_at > now() - interval '1 hour' AND
+            (email = lower($1) OR client_ip = $2)
+        "#,
+        email,
+        client_ip,
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+    Ok(recent.per_email >= MAX_RESENDS_PER_EMAIL_PER_HOUR
+        || recent.per_client >= MAX_RESENDS_PER_CLIENT_PER_HOUR)
+}
+
+// function to record an accepted resend request, so that it counts towards the limits
+#[tracing::instrument(name = "Record a resend request", skip(transaction, email))]
+pub(crate) async fn record_resend_request(
+    transaction: &mut Transaction<'_, Postgres>,
+    email: &str,
+    client_ip: &str,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO confirmation_resend_requests (email, client_ip, requested_at)
+        VALUES (lower($1), $2, now())
+        "#,
+        email,
+        client_ip,
+    );
+    transaction.execute(query).await?;
+    Ok(())
+}
+
+// function to look up a subscriber still waiting to confirm under the given address
+#[tracing::instrument(
+    name = "Get pending subscriber_id from email",
+    skip(transaction, email)
+)]
+async fn get_pending_subscriber_id(
+    transaction: &mut Transaction<'_, Postgres>,
+    email: &SubscriberEmail,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"SELECT id FROM subscriptions WHERE email = $1 AND status = 'pending_confirmation'"#,
+        email.as_ref(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+    Ok(result.map(|r| r.id))
+}
+
+// resend confirmation form handler
+#[tracing::instrument(name = "Resend confirmation form", skip(flashes))]
+pub async fn resend_confirmation_form(
+    flashes: IncomingFlashes,
+) -> (IncomingFlashes, ResendConfirmationTemplate) {
+    // process any incoming flash messages
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    // render the resend confirmation form from its associated Askama template
+    let resend_confirmation_template = ResendConfirmationTemplate { flash_msg };
+
+    (flashes, resend_confirmation_template)
+}
+
+// resend confirmation handler, the response is the same whether or not the address is awaiting confirmation
+#[tracing::instrument(
+    name = "Resending a confirmation email",
+    skip(app_state, flash, headers, resend_data),
+    fields(subscriber_email = %resend_data.email, client_ip = tracing::field::Empty)
+)]
+pub async fn resend_confirmation(
+    State(app_state): State<AppState>,
+    ConnectInfo(peer_address): ConnectInfo<SocketAddr>,
+    flash: Flash,
+    headers: HeaderMap,
+    resend_data: Form<ResendData>,
+) -> Result<Response, SubscribeError> {
+    let email =
+        SubscriberEmail::parse(resend_data.0.email).map_err(SubscribeError::ValidationError)?;
+    let client_ip = client_ip(&headers, peer_address, &app_state.trusted_proxy_hops);
+    tracing::Span::current().record("client_ip", tracing::field::display(&client_ip));
+
+    // the limits are checked and the request recorded in one transaction, holding the locks on both
+    let mut transaction = app_state
+        .db_pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool.")?;
+    lock_resend_limits(&mut transaction, email.as_ref(), &client_ip)
+        .await
+        .context("Failed to lock the resend request limits.")?;
+    if is_throttled(&mut transaction, email.as_ref(), &client_ip)
+        .await
+        .context("Failed to check the resend request limits.")?
+    {
+        tracing::warn!("Too many confirmation emails were requested.");
+        let flash = flash
+            .error("Too many confirmation emails were requested recently, please try again later.");
+        return Ok((flash, Redirect::to("/subscriptions/resend")).into_response());
+    }
+    record_resend_request(&mut transaction, email.as_ref(), &client_ip)
+        .await
+        .context("Failed to record the resend request.")?;
+    let subscriber_id = if is_suppressed(&app_state.db_pool, email.as_ref())
+        .await
+        .context("Failed to check the suppression list.")?
+    {
+        None
+    } else {
+        get_pending_subscriber_id(&mut transaction, &email)
+            .await
+            .context("Failed to look up a pending subscriber in the database.")?
+    };
+    match subscriber_id {
+        Some(subscriber_id) => {
+            let subscription_token = generate_subscription_token();
+            store_token(&mut transaction, subscriber_id, &subscription_token)
+                .await
+                .context("Failed to store the confirmation token for a pending subscriber.")?;
+            enqueue_confirmation_email(
+                &mut transaction,
+                &email,
+                &app_state.bs_url.0,
+                &subscription_token,
+            )
+            .await
+            .context("Failed to queue a confirmation email.")?;
+        }
+        None => tracing::info!("No subscriber is awaiting confirmation under this address."),
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to resend a confirmation email.")?;
+
+    let pending_confirmation_template = PendingConfirmationTemplate {
+        flash_msg: String::new(),
+    };
+
+    Ok(pending_confirmation_template.into_response())
+}
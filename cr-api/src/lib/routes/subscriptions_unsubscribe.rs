@@ -0,0 +1,154 @@
+// src/routes/subscriptions_unsubscribe.rs
+
+// dependencies
+use crate::domain::{
+    EmailBody, SubscriberEmail, UnsubscribeFormTemplate, UnsubscribeToken, UnsubscribedEmailHtml,
+    UnsubscribedEmailText, UnsubscribedTemplate,
+};
+use crate::email_outbox_worker::enqueue_email;
+use crate::errors::UnsubscribeError;
+use crate::state::AppState;
+use crate::suppressions::{suppress, SuppressionSource};
+use anyhow::Context;
+use axum::extract::{Query, State};
+use axum_flash::IncomingFlashes;
+use serde::Deserialize;
+use sqlx::{Postgres, Transaction};
+use std::fmt::Write;
+use uuid::Uuid;
+
+// struct to represent the query parameters, which includes an unsubscribe token
+#[derive(Debug, Deserialize)]
+pub struct UnsubscribeParameters {
+    token: String,
+}
+
+// a struct to represent a subscriber who has just been marked as unsubscribed
+pub struct UnsubscribedSubscriber {
+    pub email: String,
+    pub was_confirmed: bool,
+}
+
+// function which marks the subscriber as unsubscribed in the database, returns their email address along with
+// whether they were confirmed until now, so that repeated clicks don't result in repeated emails
+#[tracing::instrument(
+    name = "Mark subscriber as unsubscribed",
+    skip(subscriber_id, transaction)
+)]
+pub async fn unsubscribe_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+) -> Result<Option<UnsubscribedSubscriber>, sqlx::Error> {
+    let subscriber = sqlx::query!(
+        r#"
+        UPDATE subscriptions SET status = 'unsubscribed'
+        FROM (SELECT id, status FROM subscriptions WHERE id = $1 FOR UPDATE) AS previous
+        WHERE subscriptions.id = previous.id
+        RETURNING subscriptions.email, previous.status AS previous_status
+        "#,
+        subscriber_id,
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+    Ok(subscriber.map(|s| UnsubscribedSubscriber {
+        email: s.email,
+        was_confirmed: s.previous_status == "confirmed",
+    }))
+}
+
+// function which writes an email to the outbox, confirming to the subscriber that they have been unsubscribed
+#[tracing::instrument(name = "Queueing an unsubscribe confirmation email", skip_all)]
+async fn enqueue_unsubscribed_email(
+    transaction: &mut Transaction<'_, Postgres>,
+    recipient: &SubscriberEmail,
+) -> Result<(), anyhow::Error> {
+    let body = EmailBody::render(UnsubscribedEmailHtml, UnsubscribedEmailText)?;
+
+    enqueue_email(
+        transaction,
+        recipient,
+        "You've been unsubscribed",
+        &body.html,
+        &body.text,
+    )
+    .await?;
+    Ok(())
+}
+
+// unsubscribe form handler, asks the subscriber to confirm before anything is changed
+#[tracing::instrument(name = "Unsubscribe form", skip(app_state, flashes))]
+pub async fn unsubscribe_form(
+    State(app_state): State<AppState>,
+    flashes: IncomingFlashes,
+    parameters: Query<UnsubscribeParameters>,
+) -> Result<(IncomingFlashes, UnsubscribeFormTemplate), UnsubscribeError> {
+    UnsubscribeToken::verify(&parameters.token, &app_state.hmac_secret.0)
+        .map_err(|e| UnsubscribeError::InvalidToken(anyhow::anyhow!(e)))?;
+
+    // process any incoming flash messages
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    // render the unsubscribe confirmation form from its associated Askama template
+    let unsubscribe_form_template = UnsubscribeFormTemplate {
+        flash_msg,
+        token: parameters.0.token,
+    };
+
+    Ok((flashes, unsubscribe_form_template))
+}
+
+// unsubscribe handler, serves both the confirmation form and one-click (RFC 8058) requests
+#[tracing::instrument(name = "Unsubscribe a subscriber", skip(app_state, flashes))]
+pub async fn unsubscribe(
+    State(app_state): State<AppState>,
+    flashes: IncomingFlashes,
+    parameters: Query<UnsubscribeParameters>,
+) -> Result<(IncomingFlashes, UnsubscribedTemplate), UnsubscribeError> {
+    let subscriber_id = UnsubscribeToken::verify(&parameters.token, &app_state.hmac_secret.0)
+        .map_err(|e| UnsubscribeError::InvalidToken(anyhow::anyhow!(e)))?;
+    let mut transaction = app_state
+        .db_pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool.")?;
+    let unsubscribed = unsubscribe_subscriber(&mut transaction, subscriber_id)
+        .await
+        .context("Failed to update the subscriber status to 'unsubscribed'.")?;
+    if let Some(unsubscribed) = unsubscribed {
+        // the address is never emailed again, signing up anew doesn't bring it back either
+        suppress(
+            &mut transaction,
+            &unsubscribed.email,
+            "unsubscribed",
+            SuppressionSource::Unsubscribe,
+        )
+        .await
+        .context("Failed to add the address to the suppression list.")?;
+        // a stored address which no longer parses simply doesn't get the confirmation email
+        if unsubscribed.was_confirmed {
+            if let Ok(email) = SubscriberEmail::parse(unsubscribed.email) {
+                enqueue_unsubscribed_email(&mut transaction, &email)
+                    .await
+                    .context("Failed to queue an unsubscribe confirmation email.")?;
+            }
+        }
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to unsubscribe a subscriber.")?;
+
+    // process any incoming flash messages
+    let mut flash_msg = String::new();
+    for (level, text) in flashes.iter() {
+        writeln!(flash_msg, "{:?}: {}\n", level, text).unwrap();
+    }
+
+    // render the unsubscribed page from its associated Askama template
+    let unsubscribed_template = UnsubscribedTemplate { flash_msg };
+
+    Ok((flashes, unsubscribed_template))
+}
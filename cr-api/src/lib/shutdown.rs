@@ -0,0 +1,91 @@
+// src/lib/shutdown.rs
+
+// dependencies
+use std::time::Duration;
+use tokio::sync::watch;
+
+// a struct to hold the receiving end of the shutdown signal shared by the server and the background workers
+#[derive(Clone, Debug)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    // returns the sender used to request shutdown together with the signal handed to each task
+    pub fn channel() -> (watch::Sender<bool>, Self) {
+        let (sender, receiver) = watch::channel(false);
+        (sender, Self(receiver))
+    }
+
+    // the shutdown counts as requested once its sender is gone as well
+    pub fn is_requested(&self) -> bool {
+        *self.0.borrow() || self.0.has_changed().is_err()
+    }
+
+    // waits until shutdown is requested
+    pub async fn requested(&mut self) {
+        let _ = self.0.wait_for(|requested| *requested).await;
+    }
+
+    // sleeps for the given duration, returns early if shutdown is requested in the meantime
+    pub async fn sleep(&mut self, duration: Duration) {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = self.requested() => {}
+        }
+    }
+}
+
+// waits for SIGINT or, on unix, SIGTERM
+pub async fn wait_for_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install the SIGINT handler.");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install the SIGTERM handler.")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+// unit tests for the shutdown signal
+#[cfg(test)]
+mod tests {
+    use super::Shutdown;
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[tokio::test(start_paused = true)]
+    async fn requesting_shutdown_cuts_a_sleep_short() {
+        let (sender, mut shutdown) = Shutdown::channel();
+        let start = Instant::now();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            sender.send(true).unwrap();
+        });
+
+        shutdown.sleep(Duration::from_secs(60 * 60)).await;
+
+        assert!(start.elapsed() < Duration::from_secs(6));
+        assert!(shutdown.is_requested());
+    }
+
+    #[test]
+    fn shutdown_is_requested_once_the_sender_is_gone() {
+        let (sender, shutdown) = Shutdown::channel();
+        assert!(!shutdown.is_requested());
+        drop(sender);
+        assert!(shutdown.is_requested());
+    }
+}
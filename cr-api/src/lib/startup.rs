@@ -8,12 +8,21 @@ use crate::configuration::DatabaseSettings;
 use crate::configuration::Settings;
 use crate::email_client::EmailClient;
 use crate::routes::{
-    admin_dashboard, change_password, change_password_form, confirm, health_check, home, log_out,
-    login, login_form, publish_newsletter, publish_newsletter_form, subscribe,
+    add_suppression, admin_confirm_subscriber, admin_dashboard, admin_delete_subscriber,
+    admin_email_form, admin_unsubscribe_subscriber, archive, archived_issue, atom_feed,
+    change_admin_email, change_password, change_password_form, confirm, create_draft, drafts,
+    edit_draft, edit_draft_form, export_subscribers, health_check, home, import_subscribers,
+    import_subscribers_form, log_out, login, login_form, new_draft_form, newsletter_issue_status,
+    postmark_webhook, preview_draft, publish_draft, publish_newsletter, publish_newsletter_form,
+    remove_draft, remove_suppression, resend_confirmation, resend_confirmation_form, rss_feed,
+    send_test_issue, subscribe, subscribers, suppressions, unsubscribe, unsubscribe_form,
 };
+use crate::shutdown::Shutdown;
 use crate::state::AppState;
 use crate::state::ApplicationBaseUrl;
 use crate::state::HmacSecret;
+use crate::state::PostmarkWebhookCredentials;
+use crate::state::TrustedProxyHops;
 use crate::telemetry::MakeRequestUuid;
 use anyhow::{Context, Error, Result};
 use axum::{
@@ -27,6 +36,7 @@ use redis_pool::RedisPool;
 use secrecy::Secret;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
+use std::net::SocketAddr;
 use tokio::net::TcpListener;
 use tower::ServiceBuilder;
 use tower_http::{
@@ -45,8 +55,9 @@ pub struct Application {
 
 // implementation block to create an instance of an Application
 impl Application {
-    // function to build a new application instance
-    pub async fn build(configuration: Settings) -> Result<Self, Error> {
+    // function to build a new application instance, the email client is shared with the background workers
+    // so that they all draw from the same email rate limit
+    pub async fn build(configuration: Settings, email_client: EmailClient) -> Result<Self, Error> {
         // Get database pool
         let connection_pool = get_connection_pool(&configuration.database);
 
@@ -59,9 +70,6 @@ impl Application {
         let session_store =
             SessionStore::<SessionRedisPool>::new(Some(redis_pool.into()), session_config).await?;
 
-        // Build an email client
-        let email_client = configuration.email_client.client();
-
         let address = format!(
             "{}:{}",
             configuration.application.host, configuration.application.port
@@ -75,6 +83,11 @@ impl Application {
             email_client,
             configuration.application.base_url,
             configuration.application.hmac_secret.into(),
+            PostmarkWebhookCredentials {
+                username: configuration.postmark_webhook.username,
+                password: configuration.postmark_webhook.password.into(),
+            },
+            TrustedProxyHops(configuration.application.trusted_proxy_hops),
             session_store,
         )
         .await
@@ -94,9 +107,25 @@ impl Application {
 
     // function to run the app until stopped
     pub async fn run_until_stopped(self) -> Result<(), Error> {
-        serve(self.listener, self.app)
-            .await
-            .context("Unable to start the app server...")?;
+        serve(
+            self.listener,
+            self.app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .context("Unable to start the app server...")?;
+        Ok(())
+    }
+
+    // function to run the app until shutdown is requested, in-flight requests are completed
+    // but no new connections are accepted after that
+    pub async fn run_until_shutdown(self, mut shutdown: Shutdown) -> Result<(), Error> {
+        serve(
+            self.listener,
+            self.app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async move { shutdown.requested().await })
+        .await
+        .context("Unable to start the app server...")?;
         Ok(())
     }
 }
@@ -114,6 +143,8 @@ pub async fn create(
     email_client: EmailClient,
     base_url: String,
     hmac_secret: Secret<String>,
+    webhook_credentials: PostmarkWebhookCredentials,
+    trusted_proxy_hops: TrustedProxyHops,
     session_store: SessionStore<SessionRedisPool>,
 ) -> Result<Router, Error> {
     // build the app state
@@ -122,18 +153,73 @@ pub async fn create(
         email_client,
         ApplicationBaseUrl(base_url),
         HmacSecret(hmac_secret),
+        webhook_credentials,
+        trusted_proxy_hops,
     );
 
     // routes and their corresponding handlers, including setup of the Redis session, tracing, state and static assets such as css
 
     // routes that don't need session support
-    let router_no_session = Router::new().route("/health_check", get(health_check));
+    let router_no_session = Router::new()
+        .route("/health_check", get(health_check))
+        .route("/feed.rss", get(rss_feed))
+        .route("/feed.atom", get(atom_feed))
+        .route("/webhooks/postmark", post(postmark_webhook));
 
     // admin section routes
     let router_for_admin_section = Router::new()
         .route("/admin/dashboard", get(admin_dashboard))
         .route("/admin/newsletter", get(publish_newsletter_form))
         .route("/admin/newsletter", post(publish_newsletter))
+        .route(
+            "/admin/newsletter/:newsletter_issue_id/status",
+            get(newsletter_issue_status),
+        )
+        .route(
+            "/admin/newsletter/:newsletter_issue_id/test",
+            post(send_test_issue),
+        )
+        .route("/admin/email", get(admin_email_form))
+        .route("/admin/email", post(change_admin_email))
+        .route("/admin/drafts", get(drafts))
+        .route("/admin/drafts", post(create_draft))
+        .route("/admin/drafts/new", get(new_draft_form))
+        .route("/admin/drafts/:newsletter_issue_id", post(edit_draft))
+        .route(
+            "/admin/drafts/:newsletter_issue_id/edit",
+            get(edit_draft_form),
+        )
+        .route(
+            "/admin/drafts/:newsletter_issue_id/preview",
+            get(preview_draft),
+        )
+        .route(
+            "/admin/drafts/:newsletter_issue_id/delete",
+            post(remove_draft),
+        )
+        .route(
+            "/admin/drafts/:newsletter_issue_id/publish",
+            post(publish_draft),
+        )
+        .route("/admin/subscribers", get(subscribers))
+        .route("/admin/subscribers/export", get(export_subscribers))
+        .route("/admin/subscribers/import", get(import_subscribers_form))
+        .route("/admin/subscribers/import", post(import_subscribers))
+        .route(
+            "/admin/subscribers/:subscriber_id/confirm",
+            post(admin_confirm_subscriber),
+        )
+        .route(
+            "/admin/subscribers/:subscriber_id/unsubscribe",
+            post(admin_unsubscribe_subscriber),
+        )
+        .route(
+            "/admin/subscribers/:subscriber_id/delete",
+            post(admin_delete_subscriber),
+        )
+        .route("/admin/suppressions", get(suppressions))
+        .route("/admin/suppressions", post(add_suppression))
+        .route("/admin/suppressions/delete", post(remove_suppression))
         .route("/admin/password", get(change_password_form))
         .route("/admin/password", post(change_password))
         .route("/admin/logout", post(log_out))
@@ -142,10 +228,16 @@ pub async fn create(
     // All routes that need a session
     let router_for_non_admin_routes = Router::new()
         .route("/", get(home))
+        .route("/issues", get(archive))
+        .route("/issues/:slug", get(archived_issue))
         .route("/login", get(login_form))
         .route("/login", post(login))
         .route("/subscriptions", post(subscribe))
         .route("/subscriptions/confirm", get(confirm))
+        .route("/subscriptions/resend", get(resend_confirmation_form))
+        .route("/subscriptions/resend", post(resend_confirmation))
+        .route("/subscriptions/unsubscribe", get(unsubscribe_form))
+        .route("/subscriptions/unsubscribe", post(unsubscribe))
         .merge(router_for_admin_section)
         .layer(SessionLayer::new(session_store));
 
@@ -17,22 +17,38 @@ pub struct ApplicationBaseUrl(pub String);
 #[derive(Debug, Clone)]
 pub struct HmacSecret(pub Secret<String>);
 
+// struct for the number of trusted proxies in front of the app, each appending to the X-Forwarded-For header
+#[derive(Debug, Clone, Copy)]
+pub struct TrustedProxyHops(pub u32);
+
+// struct for the credentials expected from the Postmark webhook
+#[derive(Debug, Clone)]
+pub struct PostmarkWebhookCredentials {
+    pub username: String,
+    pub password: Secret<String>,
+}
+
 // struct for the AppState type, derives Clone as well as FromRef
 #[derive(Clone, Debug, FromRef)]
 pub struct AppState {
     pub db_pool: PgPool,
     pub em_client: EmailClient,
     pub bs_url: ApplicationBaseUrl,
+    pub hmac_secret: HmacSecret,
     pub flash_config: axum_flash::Config,
+    pub webhook_credentials: PostmarkWebhookCredentials,
+    pub trusted_proxy_hops: TrustedProxyHops,
 }
 
-// implementation block for AppState, create a state using a database pool, email client, application base url, and flash message config
+// implementation block for AppState, create a state using a database pool, email client, application base url, hmac secret, flash message config, webhook credentials and trusted proxy hops
 impl AppState {
     pub fn create_state(
         pool: PgPool,
         client: EmailClient,
         url: ApplicationBaseUrl,
         hmac_secret: HmacSecret,
+        webhook_credentials: PostmarkWebhookCredentials,
+        trusted_proxy_hops: TrustedProxyHops,
     ) -> Self {
         Self {
             db_pool: pool,
@@ -41,6 +57,9 @@ impl AppState {
             flash_config: axum_flash::Config::new(Key::from(
                 hmac_secret.0.expose_secret().as_bytes(),
             )),
+            hmac_secret,
+            webhook_credentials,
+            trusted_proxy_hops,
         }
     }
 }
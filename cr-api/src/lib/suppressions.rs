@@ -0,0 +1,78 @@
+// src/lib/suppressions.rs
+
+// the suppression list, addresses on it are never emailed, whatever the state of their subscription
+
+// dependencies
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+
+// an enum to represent where a suppression came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuppressionSource {
+    Admin,
+    Postmark,
+    Unsubscribe,
+}
+
+// implementation to return the string stored in the suppressions table for each source
+impl SuppressionSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuppressionSource::Admin => "admin",
+            SuppressionSource::Postmark => "postmark",
+            SuppressionSource::Unsubscribe => "unsubscribe",
+        }
+    }
+}
+
+// function to check whether an address is on the suppression list
+#[tracing::instrument(skip(pool))]
+pub async fn is_suppressed(pool: &PgPool, email: &str) -> Result<bool, sqlx::Error> {
+    let suppressed = sqlx::query!(
+        r#"SELECT EXISTS (SELECT 1 FROM suppressions WHERE email = lower($1)) as "value!""#,
+        email,
+    )
+    .fetch_one(pool)
+    .await?
+    .value;
+    Ok(suppressed)
+}
+
+// function to check whether transactional emails to an address are held back, an address which is only on the
+// suppression list because its owner unsubscribed can still be told about it
+#[tracing::instrument(skip(pool))]
+pub async fn blocks_transactional_email(pool: &PgPool, email: &str) -> Result<bool, sqlx::Error> {
+    let blocked = sqlx::query!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM suppressions WHERE email = lower($1) AND source <> 'unsubscribe'
+        ) as "value!"
+        "#,
+        email,
+    )
+    .fetch_one(pool)
+    .await?
+    .value;
+    Ok(blocked)
+}
+
+// function to add an address to the suppression list, an address which is already on it keeps its original entry
+#[tracing::instrument(skip(transaction))]
+pub async fn suppress(
+    transaction: &mut Transaction<'_, Postgres>,
+    email: &str,
+    reason: &str,
+    source: SuppressionSource,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO suppressions (email, reason, source, created_at)
+        VALUES (lower($1), $2, $3, now())
+        ON CONFLICT (email) DO NOTHING
+        "#,
+        email,
+        reason,
+        source.as_str(),
+    );
+    transaction.execute(query).await?;
+    Ok(())
+}
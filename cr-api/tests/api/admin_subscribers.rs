@@ -0,0 +1,664 @@
+// tests/api/admin_subscribers.rs
+
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+// stores a subscriber straight in the database, the later the index the more recent the subscription
+async fn store_subscriber(
+    app: &TestApp,
+    email: &str,
+    name: &str,
+    status: &str,
+    index: i64,
+) -> Uuid {
+    let subscriber_id = Uuid::new_v4();
+    sqlx::query!(
+        "INSERT INTO subscriptions (id, email, name, subscribed_at, status)
+        VALUES ($1, $2, $3, $4, $5)",
+        subscriber_id,
+        email,
+        name,
+        Utc::now() - Duration::days(100) + Duration::minutes(index),
+        status,
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store test subscriber.");
+    subscriber_id
+}
+
+async fn subscriber_status(app: &TestApp, subscriber_id: Uuid) -> Option<String> {
+    sqlx::query!(
+        "SELECT status FROM subscriptions WHERE id = $1",
+        subscriber_id
+    )
+    .fetch_optional(&app.db_pool)
+    .await
+    .expect("Failed to fetch the subscriber.")
+    .map(|r| r.status)
+}
+
+async fn audit_log(app: &TestApp) -> Vec<(String, String)> {
+    sqlx::query!("SELECT action, subscriber_email FROM admin_audit_log ORDER BY recorded_at")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch the audit log.")
+        .into_iter()
+        .map(|r| (r.action, r.subscriber_email))
+        .collect()
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_manage_subscribers() {
+    // Arrange
+    let app = spawn_app().await;
+    let subscriber_id = store_subscriber(
+        &app,
+        "ursula@example.com",
+        "Ursula",
+        "pending_confirmation",
+        0,
+    )
+    .await;
+
+    // Act
+    let response = app.get_subscribers("").await;
+    let confirm_response = app.post_subscriber_action(&subscriber_id, "confirm").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert_is_redirect_to(&confirm_response, "/login");
+    assert_eq!(
+        subscriber_status(&app, subscriber_id).await.unwrap(),
+        "pending_confirmation"
+    );
+}
+
+#[tokio::test]
+async fn subscribers_are_listed_most_recent_first() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    store_subscriber(&app, "ursula@example.com", "Ursula Le Guin", "confirmed", 0).await;
+    store_subscriber(
+        &app,
+        "octavia@example.com",
+        "Octavia Butler",
+        "pending_confirmation",
+        1,
+    )
+    .await;
+
+    // Act
+    let html_page = app.get_subscribers_html("").await;
+
+    // Assert
+    assert!(html_page.contains("2 subscriber(s) found."));
+    let octavia = html_page.find("octavia@example.com").unwrap();
+    let ursula = html_page.find("ursula@example.com").unwrap();
+    assert!(octavia < ursula);
+    assert!(html_page.contains("Octavia Butler"));
+    assert!(html_page.contains("pending_confirmation"));
+}
+
+#[tokio::test]
+async fn subscribers_can_be_searched_and_filtered_by_status() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    store_subscriber(&app, "ursula@example.com", "Ursula Le Guin", "confirmed", 0).await;
+    store_subscriber(
+        &app,
+        "octavia@example.com",
+        "Octavia Butler",
+        "pending_confirmation",
+        1,
+    )
+    .await;
+    store_subscriber(&app, "le_guin_fan@example.com", "Fan", "unsubscribed", 2).await;
+    store_subscriber(&app, "bounced@example.com", "Bounced", "bounced", 3).await;
+    store_subscriber(
+        &app,
+        "complained@example.com",
+        "Complained",
+        "complained",
+        4,
+    )
+    .await;
+
+    // Act
+    let by_name = app.get_subscribers_html("?search=le+GUIN").await;
+    let by_status = app
+        .get_subscribers_html("?status=pending_confirmation")
+        .await;
+    let both = app
+        .get_subscribers_html("?search=le&status=unsubscribed")
+        .await;
+    let wildcard = app.get_subscribers_html("?search=_").await;
+    let bounced = app.get_subscribers_html("?status=bounced").await;
+    let complained = app.get_subscribers_html("?status=complained").await;
+    let invalid_status = app.get_subscribers("?status=bogus").await;
+
+    // Assert
+    assert!(by_name.contains("ursula@example.com"));
+    assert!(!by_name.contains("octavia@example.com"));
+    assert!(by_status.contains("octavia@example.com"));
+    assert!(!by_status.contains("ursula@example.com"));
+    assert!(both.contains("le_guin_fan@example.com"));
+    assert!(!both.contains("ursula@example.com"));
+    // a wildcard in the search box only matches itself
+    assert!(wildcard.contains("1 subscriber(s) found."));
+    assert!(bounced.contains("1 subscriber(s) found."));
+    assert!(bounced.contains("bounced@example.com"));
+    assert!(complained.contains("1 subscriber(s) found."));
+    assert!(complained.contains("complained@example.com"));
+    assert_eq!(invalid_status.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn the_subscriber_list_is_paginated_and_keeps_the_filters() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    for index in 0..30 {
+        let email = format!("reader{:02}@example.com", index);
+        store_subscriber(&app, &email, "Reader", "confirmed", index).await;
+    }
+
+    // Act
+    let first_page = app.get_subscribers_html("?status=confirmed").await;
+    let second_page = app.get_subscribers_html("?status=confirmed&page=2").await;
+
+    // Assert
+    assert!(first_page.contains("30 subscriber(s) found."));
+    assert!(first_page.contains("reader29@example.com"));
+    assert!(!first_page.contains("reader04@example.com"));
+    assert!(first_page.contains("href=\"/admin/subscribers?status=confirmed&amp;page=2\""));
+    assert!(second_page.contains("reader04@example.com"));
+    assert!(!second_page.contains("reader05@example.com"));
+    assert!(second_page.contains("href=\"/admin/subscribers?status=confirmed&amp;page=1\""));
+}
+
+#[tokio::test]
+async fn a_page_number_too_large_to_list_is_rejected_with_a_400() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app.get_subscribers(&format!("?page={}", i64::MAX)).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn an_admin_can_confirm_and_unsubscribe_a_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let subscriber_id = store_subscriber(
+        &app,
+        "ursula@example.com",
+        "Ursula",
+        "pending_confirmation",
+        0,
+    )
+    .await;
+
+    // Act - Part 1 - Confirm
+    let response = app.post_subscriber_action(&subscriber_id, "confirm").await;
+    assert_is_redirect_to(&response, "/admin/subscribers");
+    assert!(app
+        .get_subscribers_html("")
+        .await
+        .contains("The subscriber has been confirmed."));
+    assert_eq!(
+        subscriber_status(&app, subscriber_id).await.unwrap(),
+        "confirmed"
+    );
+
+    // Act - Part 2 - Confirming again changes nothing
+    app.post_subscriber_action(&subscriber_id, "confirm").await;
+    assert!(app
+        .get_subscribers_html("")
+        .await
+        .contains("The subscriber is already confirmed."));
+
+    // Act - Part 3 - Unsubscribe
+    let response = app
+        .post_subscriber_action(&subscriber_id, "unsubscribe")
+        .await;
+    assert_is_redirect_to(&response, "/admin/subscribers");
+
+    // Assert
+    assert_eq!(
+        subscriber_status(&app, subscriber_id).await.unwrap(),
+        "unsubscribed"
+    );
+    assert_eq!(
+        audit_log(&app).await,
+        vec![
+            (
+                "confirm_subscriber".to_string(),
+                "ursula@example.com".to_string()
+            ),
+            (
+                "unsubscribe_subscriber".to_string(),
+                "ursula@example.com".to_string()
+            ),
+        ]
+    );
+    let html_page = app.get_subscribers_html("").await;
+    assert!(html_page.contains("unsubscribe_subscriber"));
+    assert!(html_page.contains(&app.test_user.username));
+}
+
+#[tokio::test]
+async fn an_address_unsubscribed_by_an_admin_is_suppressed_and_cannot_be_confirmed() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let subscriber_id =
+        store_subscriber(&app, "ursula@example.com", "Ursula", "confirmed", 0).await;
+
+    // Act - Part 1 - Unsubscribe
+    app.post_subscriber_action(&subscriber_id, "unsubscribe")
+        .await;
+    let suppression =
+        sqlx::query!("SELECT source FROM suppressions WHERE email = 'ursula@example.com'")
+            .fetch_one(&app.db_pool)
+            .await
+            .expect("Failed to fetch the suppression.");
+    assert_eq!(suppression.source, "admin");
+
+    // Act - Part 2 - Confirming the suppressed address is refused
+    let response = app.post_subscriber_action(&subscriber_id, "confirm").await;
+    assert_is_redirect_to(&response, "/admin/subscribers");
+
+    // Assert
+    assert!(app
+        .get_subscribers_html("")
+        .await
+        .contains("on the suppression list, it can&#x27;t be confirmed."));
+    assert_eq!(
+        subscriber_status(&app, subscriber_id).await.unwrap(),
+        "unsubscribed"
+    );
+}
+
+#[tokio::test]
+async fn a_confirmation_link_sent_before_an_admin_action_no_longer_works() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let subscriber_id = store_subscriber(
+        &app,
+        "ursula@example.com",
+        "Ursula",
+        "pending_confirmation",
+        0,
+    )
+    .await;
+    sqlx::query!(
+        "INSERT INTO subscription_tokens (subscription_token, subscriber_id) VALUES ('a-token', $1)",
+        subscriber_id,
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store the subscription token.");
+
+    // Act
+    app.post_subscriber_action(&subscriber_id, "unsubscribe")
+        .await;
+    let response = reqwest::get(format!(
+        "{}/subscriptions/confirm?subscription_token=a-token",
+        app.address
+    ))
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 410);
+    assert_eq!(
+        subscriber_status(&app, subscriber_id).await.unwrap(),
+        "unsubscribed"
+    );
+}
+
+#[tokio::test]
+async fn an_admin_can_delete_a_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let subscriber_id = store_subscriber(
+        &app,
+        "ursula@example.com",
+        "Ursula",
+        "pending_confirmation",
+        0,
+    )
+    .await;
+    sqlx::query!(
+        "INSERT INTO subscription_tokens (subscription_token, subscriber_id) VALUES ('a-token', $1)",
+        subscriber_id,
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store the subscription token.");
+
+    // Act
+    let response = app.post_subscriber_action(&subscriber_id, "delete").await;
+    let second_response = app.post_subscriber_action(&subscriber_id, "delete").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/subscribers");
+    assert_is_redirect_to(&second_response, "/admin/subscribers");
+    assert!(subscriber_status(&app, subscriber_id).await.is_none());
+    let html_page = app.get_subscribers_html("").await;
+    assert!(html_page.contains("There is no subscriber with the provided id."));
+    assert_eq!(
+        audit_log(&app).await,
+        vec![(
+            "delete_subscriber".to_string(),
+            "ursula@example.com".to_string()
+        )]
+    );
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_export_or_import_subscribers() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let export_response = app.get_subscribers_export().await;
+    let import_response = app
+        .post_subscribers_import("email,name\nursula@example.com,Ursula\n", "confirmed")
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&export_response, "/login");
+    assert_is_redirect_to(&import_response, "/login");
+    assert!(audit_log(&app).await.is_empty());
+}
+
+#[tokio::test]
+async fn subscribers_are_exported_as_csv() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    store_subscriber(
+        &app,
+        "octavia@example.com",
+        "Butler, Octavia",
+        "pending_confirmation",
+        1,
+    )
+    .await;
+    store_subscriber(
+        &app,
+        "ursula@example.com",
+        "Ursula \"Le\" Guin",
+        "confirmed",
+        0,
+    )
+    .await;
+
+    // Act
+    let response = app.get_subscribers_export().await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/csv; charset=utf-8"
+    );
+    assert!(response.headers()["content-disposition"]
+        .to_str()
+        .unwrap()
+        .starts_with("attachment; filename=\"subscribers-"));
+    let csv = response.text().await.unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "email,name,status,subscribed_at");
+    assert!(lines[1].starts_with("ursula@example.com,\"Ursula \"\"Le\"\" Guin\",confirmed,"));
+    assert!(lines[2].starts_with("octavia@example.com,\"Butler, Octavia\",pending_confirmation,"));
+}
+
+#[tokio::test]
+async fn fields_which_would_start_a_formula_are_escaped_in_the_export() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let test_cases = [
+        ("equals@example.com", "=1+2", "'=1+2"),
+        ("plus@example.com", "+1", "'+1"),
+        ("minus@example.com", "-1", "'-1"),
+        ("at@example.com", "@SUM", "'@SUM"),
+        ("plain@example.com", "Ursula", "Ursula"),
+    ];
+    for (index, (email, name, _)) in test_cases.iter().enumerate() {
+        store_subscriber(&app, email, name, "confirmed", index as i64).await;
+    }
+
+    // Act
+    let csv = app.get_subscribers_export().await.text().await.unwrap();
+
+    // Assert
+    for (email, _, exported_name) in test_cases {
+        let expected = format!("{},{},confirmed,", email, exported_name);
+        assert!(
+            csv.lines().any(|line| line.starts_with(&expected)),
+            "{} is missing from {}",
+            expected,
+            csv
+        );
+    }
+}
+
+#[tokio::test]
+async fn importing_as_confirmed_reports_each_row_left_out() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    store_subscriber(&app, "ursula@example.com", "Ursula", "unsubscribed", 0).await;
+    sqlx::query!(
+        "INSERT INTO suppressions (email, reason, source, created_at)
+        VALUES ('bounced@example.com', 'bounced', 'admin', now())"
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store the suppression.");
+    let csv = "\
+Name,Email,Notes
+Octavia Butler,octavia@example.com,kept
+Ursula,ursula@example.com,already there
+Octavia Again,octavia@example.com,twice in the file
+Nobody,not-an-email,
+,nameless@example.com,
+Bounced,bounced@example.com,
+\"N. K. Jemisin\", nk@example.com ,
+";
+
+    // Act
+    let response = app.post_subscribers_import(csv, "confirmed").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("2 subscriber(s) imported as confirmed."));
+    assert!(html_page.contains("ursula@example.com is already subscribed."));
+    assert!(html_page.contains("<td>4</td>"));
+    assert!(html_page.contains("bounced@example.com is on the suppression list."));
+    assert!(html_page.contains("not-an-email is not a valid subscriber email."));
+    assert!(html_page.contains("<td>5</td>"));
+    assert!(html_page.contains(" is not a valid subscriber name."));
+    let imported = sqlx::query!(
+        "SELECT email, name, status FROM subscriptions WHERE email <> 'ursula@example.com' ORDER BY email"
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .expect("Failed to fetch the imported subscribers.");
+    assert_eq!(imported.len(), 2);
+    assert_eq!(imported[0].email, "nk@example.com");
+    assert_eq!(imported[0].name, "N. K. Jemisin");
+    assert_eq!(imported[1].email, "octavia@example.com");
+    assert_eq!(imported[1].name, "Octavia Butler");
+    assert!(imported.iter().all(|r| r.status == "confirmed"));
+    // the existing subscriber is left alone
+    assert_eq!(
+        sqlx::query!("SELECT status FROM subscriptions WHERE email = 'ursula@example.com'")
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap()
+            .status,
+        "unsubscribed"
+    );
+    // every imported subscriber is recorded in the audit log
+    let audit_entries = audit_log(&app).await;
+    assert_eq!(audit_entries.len(), 2);
+    assert!(audit_entries
+        .iter()
+        .all(|(action, _)| action == "import_subscriber"));
+    app.dispatch_all_outbox_emails().await;
+    assert!(app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn importing_as_pending_sends_a_confirmation_email_to_each_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_subscribers_import(
+            "email,name\noctavia@example.com,Octavia\nursula@example.com,Ursula\n",
+            "pending_confirmation",
+        )
+        .await;
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("2 subscriber(s) imported as pending_confirmation."));
+    let email_request = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let confirmation_link = app.get_confirmation_links(&email_request).html;
+    reqwest::get(confirmation_link)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+    let confirmed = sqlx::query!(
+        r#"SELECT COUNT(*) as "value!" FROM subscriptions WHERE status = 'confirmed'"#
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(confirmed.value, 1);
+}
+
+#[tokio::test]
+async fn an_export_can_be_imported_again() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    store_subscriber(
+        &app,
+        "octavia@example.com",
+        "Butler, Octavia",
+        "confirmed",
+        0,
+    )
+    .await;
+    store_subscriber(&app, "formula@example.com", "=1+2", "confirmed", 1).await;
+    let csv = app.get_subscribers_export().await.text().await.unwrap();
+    sqlx::query!("DELETE FROM subscriptions")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let html_page = app
+        .post_subscribers_import(&csv, "confirmed")
+        .await
+        .text()
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(html_page.contains("2 subscriber(s) imported as confirmed."));
+    let imported = sqlx::query!("SELECT name FROM subscriptions ORDER BY email")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(imported[0].name, "=1+2");
+    assert_eq!(imported[1].name, "Butler, Octavia");
+}
+
+#[tokio::test]
+async fn an_import_without_the_expected_columns_or_status_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let test_cases = vec![
+        (
+            "address,full name\nursula@example.com,Ursula\n",
+            "confirmed",
+            "The CSV file needs a header row with an email and a name column.",
+        ),
+        (
+            "email,name\nursula@example.com,Ursula\n",
+            "bounced",
+            "Choose whether the subscribers are imported as confirmed or sent a confirmation email.",
+        ),
+        ("", "confirmed", "Choose a CSV file to import."),
+    ];
+
+    for (csv, status, error_message) in test_cases {
+        // Act
+        let response = app.post_subscribers_import(csv, status).await;
+
+        // Assert
+        assert_is_redirect_to(&response, "/admin/subscribers/import");
+        let html_page = app
+            .api_client
+            .get(format!("{}/admin/subscribers/import", app.address))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(html_page.contains(error_message));
+    }
+    let n_subscribers = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM subscriptions"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(n_subscribers.value, 0);
+}
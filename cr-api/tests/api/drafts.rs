@@ -0,0 +1,303 @@
+// tests/api/drafts.rs
+
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+async fn create_confirmed_subscriber(app: &TestApp) {
+    sqlx::query!(
+        "INSERT INTO subscriptions (id, email, name, subscribed_at, status)
+        VALUES ($1, 'ursula_le_guin@gmail.com', 'le guin', now(), 'confirmed')",
+        Uuid::new_v4(),
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store test subscriber.");
+}
+
+// creates a draft through the admin form and returns its id, taken from the redirect location
+async fn create_draft(app: &TestApp, html_content: &str) -> String {
+    let response = app
+        .post_create_draft(&serde_json::json!({
+            "title": "Draft title",
+            "text_content": "Draft body as plain text",
+            "html_content": html_content,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 303);
+    let location = response
+        .headers()
+        .get("Location")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    location
+        .trim_start_matches("/admin/drafts/")
+        .trim_end_matches("/edit")
+        .to_string()
+}
+
+async fn issue_status(app: &TestApp, draft_id: &str) -> String {
+    sqlx::query!(
+        "SELECT status FROM newsletter_issues WHERE newsletter_issue_id = $1",
+        Uuid::parse_str(draft_id).unwrap(),
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch the newsletter issue.")
+    .status
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_manage_drafts() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_drafts().await;
+    let create_response = app
+        .post_create_draft(&serde_json::json!({
+            "title": "Draft title",
+            "text_content": "Draft body as plain text",
+            "html_content": "<p>Draft body as HTML</p>",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert_is_redirect_to(&create_response, "/login");
+}
+
+#[tokio::test]
+async fn a_saved_draft_is_listed_and_not_delivered() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let draft_id = create_draft(&app, "<p>Draft body as HTML</p>").await;
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let html_page = app.get_drafts_html().await;
+    assert!(html_page.contains("Draft title"));
+    assert!(html_page.contains(&format!("/admin/drafts/{}/edit", draft_id)));
+    assert_eq!(issue_status(&app, &draft_id).await, "draft");
+}
+
+#[tokio::test]
+async fn a_draft_can_be_edited_and_previewed_as_it_will_be_sent() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let draft_id = create_draft(&app, "<p>First version</p>").await;
+
+    // Act
+    let response = app
+        .post_edit_draft(
+            &draft_id,
+            &serde_json::json!({
+                "title": "Edited title",
+                "text_content": "Edited body as plain text",
+                "html_content": "<p>Edited <em>version</em></p>",
+            }),
+        )
+        .await;
+    assert_is_redirect_to(&response, &format!("/admin/drafts/{}/edit", draft_id));
+    let preview = app.get_draft_preview(&draft_id).await;
+
+    // Assert
+    assert_eq!(preview.status().as_u16(), 200);
+    let html_page = preview.text().await.unwrap();
+    assert!(html_page.contains("Edited title"));
+    assert!(html_page.contains("<p>Edited <em>version</em></p>"));
+    assert!(!html_page.contains("First version"));
+}
+
+#[tokio::test]
+async fn editing_a_draft_keeps_its_creation_time_and_updates_its_modification_time() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let draft_id = create_draft(&app, "<p>First version</p>").await;
+    let timestamps = || {
+        sqlx::query!(
+            "SELECT created_at, updated_at FROM newsletter_issues WHERE newsletter_issue_id = $1",
+            Uuid::parse_str(&draft_id).unwrap(),
+        )
+        .fetch_one(&app.db_pool)
+    };
+    let before = timestamps().await.expect("Failed to fetch the draft.");
+
+    // Act
+    app.post_edit_draft(
+        &draft_id,
+        &serde_json::json!({
+            "title": "Edited title",
+            "text_content": "Edited body as plain text",
+            "html_content": "<p>Edited version</p>",
+        }),
+    )
+    .await;
+
+    // Assert
+    let after = timestamps().await.expect("Failed to fetch the draft.");
+    assert_eq!(after.created_at, before.created_at);
+    assert!(after.updated_at > before.updated_at);
+}
+
+#[tokio::test]
+async fn previewing_an_unknown_draft_is_a_404() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app.get_draft_preview(&Uuid::new_v4().to_string()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn a_deleted_draft_is_gone() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let draft_id = create_draft(&app, "<p>Draft body as HTML</p>").await;
+
+    // Act
+    let response = app.post_delete_draft(&draft_id).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/drafts");
+    assert!(app
+        .get_drafts_html()
+        .await
+        .contains("The draft has been deleted."));
+    let preview = app.get_draft_preview(&draft_id).await;
+    assert_eq!(preview.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn publishing_a_draft_delivers_it_once() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let draft_id = create_draft(&app, "<p>Draft body as HTML</p>").await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - Part 1 - Publish the draft
+    let publish_request_body = serde_json::json!({
+        "idempotency_key": Uuid::new_v4().to_string()
+    });
+    let response = app
+        .post_publish_draft(&draft_id, &publish_request_body)
+        .await;
+    assert_is_redirect_to(&response, "/admin/drafts");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_drafts_html().await;
+    assert!(
+        html_page.contains("The newsletter issue has been accepted - emails will go out shortly.")
+    );
+
+    // Act - Part 3 - Submit the publish form again
+    let response = app
+        .post_publish_draft(&draft_id, &publish_request_body)
+        .await;
+    assert_is_redirect_to(&response, "/admin/drafts");
+
+    // Assert
+    app.dispatch_all_pending_emails().await;
+    assert_eq!(issue_status(&app, &draft_id).await, "published");
+    assert!(!app.get_drafts_html().await.contains("Draft title"));
+    // Mock verifies on Drop that we have sent the newsletter email once
+}
+
+#[tokio::test]
+async fn an_incomplete_draft_cannot_be_published() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let draft_id = create_draft(&app, "").await;
+
+    // Act
+    let response = app
+        .post_publish_draft(
+            &draft_id,
+            &serde_json::json!({ "idempotency_key": Uuid::new_v4().to_string() }),
+        )
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, &format!("/admin/drafts/{}/edit", draft_id));
+    assert_eq!(issue_status(&app, &draft_id).await, "draft");
+}
+
+#[tokio::test]
+async fn a_draft_fixed_after_a_failed_publish_can_be_published_with_the_same_request() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let draft_id = create_draft(&app, "<p>Hi {{ first_name }}</p>").await;
+    let publish_request_body = serde_json::json!({ "idempotency_key": Uuid::new_v4().to_string() });
+    let response = app
+        .post_publish_draft(&draft_id, &publish_request_body)
+        .await;
+    assert_is_redirect_to(&response, &format!("/admin/drafts/{}/edit", draft_id));
+
+    // Act
+    app.post_edit_draft(
+        &draft_id,
+        &serde_json::json!({
+            "title": "Draft title",
+            "text_content": "Draft body as plain text",
+            "html_content": "<p>Hi {{ name }}</p>",
+        }),
+    )
+    .await;
+    let response = app
+        .post_publish_draft(&draft_id, &publish_request_body)
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/drafts");
+    assert_eq!(issue_status(&app, &draft_id).await, "published");
+}
+
+#[tokio::test]
+async fn a_draft_with_unknown_placeholders_cannot_be_published() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let draft_id = create_draft(&app, "<p>Hi {{ first_name }}</p>").await;
+
+    // Act
+    let response = app
+        .post_publish_draft(
+            &draft_id,
+            &serde_json::json!({ "idempotency_key": Uuid::new_v4().to_string() }),
+        )
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, &format!("/admin/drafts/{}/edit", draft_id));
+    assert_eq!(issue_status(&app, &draft_id).await, "draft");
+}
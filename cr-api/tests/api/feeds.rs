@@ -0,0 +1,123 @@
+// tests/api/feeds.rs
+
+use crate::helpers::{spawn_app, TestApp};
+use chrono::{TimeZone, Utc};
+use uuid::Uuid;
+
+// stores a published issue straight in the database, published on the given day of January 2026
+async fn store_published_issue(
+    app: &TestApp,
+    title: &str,
+    slug: &str,
+    html_content: &str,
+    day: u32,
+) {
+    sqlx::query!(
+        "INSERT INTO newsletter_issues
+            (newsletter_issue_id, title, text_content, html_content, status, published_at, slug)
+        VALUES ($1, $2, 'Newsletter body as plain text', $3, 'published', $4, $5)",
+        Uuid::new_v4(),
+        title,
+        html_content,
+        Utc.with_ymd_and_hms(2026, 1, day, 12, 0, 0).unwrap(),
+        slug,
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store the newsletter issue.");
+}
+
+#[tokio::test]
+async fn the_feeds_are_served_with_their_content_types() {
+    // Arrange
+    let app = spawn_app().await;
+
+    for (feed, content_type) in [
+        ("rss", "application/rss+xml; charset=utf-8"),
+        ("atom", "application/atom+xml; charset=utf-8"),
+    ] {
+        // Act
+        let response = app.get_feed(feed, &[]).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(response.headers()["Content-Type"], content_type);
+        assert!(response.headers().contains_key("ETag"));
+    }
+}
+
+#[tokio::test]
+async fn the_feeds_list_published_issues_with_permalinks_and_escaped_content() {
+    // Arrange
+    let app = spawn_app().await;
+    store_published_issue(
+        &app,
+        "Tips & tricks",
+        "tips-and-tricks",
+        "<p>Hi {{ name }} &amp; co</p>",
+        1,
+    )
+    .await;
+    let permalink = format!("{}/issues/tips-and-tricks", app.base_url.0);
+
+    // Act
+    let rss = app.get_feed("rss", &[]).await.text().await.unwrap();
+    let atom = app.get_feed("atom", &[]).await.text().await.unwrap();
+
+    // Assert
+    assert!(rss.contains("<title>Tips &amp; tricks</title>"));
+    assert!(rss.contains(&format!("<link>{}</link>", permalink)));
+    assert!(rss.contains("<pubDate>Thu, 1 Jan 2026 12:00:00 +0000</pubDate>"));
+    assert!(rss.contains("&lt;p&gt;Hi reader &amp;amp; co&lt;/p&gt;"));
+    assert!(atom.contains(&format!("<id>{}</id>", permalink)));
+    assert!(atom.contains("<published>2026-01-01T12:00:00+00:00</published>"));
+    assert!(atom.contains("&lt;p&gt;Hi reader &amp;amp; co&lt;/p&gt;"));
+    assert!(!atom.contains("<p>"));
+}
+
+#[tokio::test]
+async fn the_feeds_are_not_sent_again_while_unchanged() {
+    // Arrange
+    let app = spawn_app().await;
+    store_published_issue(&app, "First issue", "first-issue", "<p>First</p>", 1).await;
+
+    for feed in ["rss", "atom"] {
+        let response = app.get_feed(feed, &[]).await;
+        let etag = response.headers()["ETag"].to_str().unwrap().to_string();
+        let last_modified = response.headers()["Last-Modified"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(last_modified, "Thu, 01 Jan 2026 12:00:00 GMT");
+
+        // Act
+        let by_etag = app.get_feed(feed, &[("If-None-Match", &etag)]).await;
+        let by_date = app
+            .get_feed(feed, &[("If-Modified-Since", &last_modified)])
+            .await;
+
+        // Assert
+        assert_eq!(by_etag.status().as_u16(), 304);
+        assert_eq!(by_date.status().as_u16(), 304);
+    }
+}
+
+#[tokio::test]
+async fn the_feeds_change_once_an_issue_is_published() {
+    // Arrange
+    let app = spawn_app().await;
+    store_published_issue(&app, "First issue", "first-issue", "<p>First</p>", 1).await;
+    let etag = app.get_feed("rss", &[]).await.headers()["ETag"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    // Act
+    store_published_issue(&app, "Second issue", "second-issue", "<p>Second</p>", 2).await;
+    let response = app.get_feed("rss", &[("If-None-Match", &etag)]).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_ne!(response.headers()["ETag"].to_str().unwrap(), etag);
+    assert!(response.text().await.unwrap().contains("Second issue"));
+}
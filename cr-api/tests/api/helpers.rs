@@ -2,11 +2,17 @@
 
 use argon2::password_hash::SaltString;
 use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
-use cr_api::configuration::{get_configuration, DatabaseSettings};
+use cr_api::configuration::{
+    get_configuration, DatabaseSettings, EmailOutboxSettings, EmailTransportKind,
+    IssueDeliverySettings, PostmarkWebhookSettings,
+};
 use cr_api::email_client::EmailClient;
-use cr_api::idempotency_cleanup_worker::remove_old_idempotency_keys;
+use cr_api::email_outbox_worker::try_send_outbox_emails;
+use cr_api::idempotency_cleanup_worker::{remove_old_idempotency_keys, remove_stale_subscriptions};
 use cr_api::issue_delivery_worker::{try_execute_task, ExecutionOutcome};
+use cr_api::newsletter_scheduling_worker::publish_due_issues;
 use cr_api::startup::{get_connection_pool, Application};
+use cr_api::state::{ApplicationBaseUrl, HmacSecret};
 use cr_api::telemetry::{get_subscriber, init_subscriber};
 use once_cell::sync::Lazy;
 use sqlx::{Connection, Executor, PgConnection, PgPool};
@@ -85,28 +91,61 @@ pub struct TestApp {
     pub test_user: TestUser,
     pub api_client: reqwest::Client,
     pub email_client: EmailClient,
+    pub issue_delivery_settings: IssueDeliverySettings,
+    pub email_outbox_settings: EmailOutboxSettings,
+    pub base_url: ApplicationBaseUrl,
+    pub hmac_secret: HmacSecret,
+    pub postmark_webhook: PostmarkWebhookSettings,
 }
 
 impl TestApp {
     pub async fn dispatch_all_pending_emails(&self) {
         loop {
-            if let ExecutionOutcome::EmptyQueue =
-                try_execute_task(&self.db_pool, &self.email_client)
-                    .await
-                    .unwrap()
+            if let ExecutionOutcome::EmptyQueue = try_execute_task(
+                &self.db_pool,
+                &self.email_client,
+                &self.issue_delivery_settings,
+                &self.base_url,
+                &self.hmac_secret,
+            )
+            .await
+            .unwrap()
             {
                 break;
             }
         }
     }
 
+    pub async fn dispatch_all_outbox_emails(&self) {
+        loop {
+            if let ExecutionOutcome::EmptyQueue = try_send_outbox_emails(
+                &self.db_pool,
+                &self.email_client,
+                &self.email_outbox_settings,
+            )
+            .await
+            .unwrap()
+            {
+                break;
+            }
+        }
+    }
+
+    pub async fn publish_scheduled_issues(&self) {
+        publish_due_issues(&self.db_pool).await.unwrap();
+    }
+
     pub async fn clean_up_idempotency(&self) {
         remove_old_idempotency_keys(&self.db_pool).await.unwrap();
     }
 
+    pub async fn clean_up_stale_subscriptions(&self) {
+        remove_stale_subscriptions(&self.db_pool).await.unwrap();
+    }
+
     pub async fn post_subscriptions(&self, body: String) -> reqwest::Response {
         self.api_client
-            .post(&format!("{}/subscriptions", &self.address))
+            .post(format!("{}/subscriptions", &self.address))
             .header("Content-Type", "application/x-www-form-urlencoded")
             .body(body)
             .send()
@@ -114,6 +153,50 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn post_postmark_webhook(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.post_postmark_webhook_as(
+            &self.postmark_webhook.username,
+            &self.postmark_webhook.password,
+            body,
+        )
+        .await
+    }
+
+    pub async fn post_postmark_webhook_as(
+        &self,
+        username: &str,
+        password: &str,
+        body: &serde_json::Value,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/webhooks/postmark", &self.address))
+            .basic_auth(username, Some(password))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_unsubscribe(&self, token: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/subscriptions/unsubscribe", &self.address))
+            .query(&[("token", token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_unsubscribe(&self, token: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/subscriptions/unsubscribe", &self.address))
+            .query(&[("token", token)])
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body("List-Unsubscribe=One-Click")
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub fn get_confirmation_links(&self, email_request: &wiremock::Request) -> ConfirmationLinks {
         let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
         // Extract the link from one of the request fields.
@@ -135,12 +218,37 @@ impl TestApp {
         ConfirmationLinks { html, plain_text }
     }
 
+    pub async fn get_resend_confirmation_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/subscriptions/resend", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_resend_confirmation(
+        &self,
+        email: &str,
+        client_ip: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/subscriptions/resend", &self.address))
+            .header("X-Forwarded-For", client_ip)
+            .form(&serde_json::json!({ "email": email }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_login<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
     {
         self.api_client
-            .post(&format!("{}/login", &self.address))
+            .post(format!("{}/login", &self.address))
             .form(body)
             .send()
             .await
@@ -149,7 +257,7 @@ impl TestApp {
 
     pub async fn get_login_html(&self) -> String {
         self.api_client
-            .get(&format!("{}/login", &self.address))
+            .get(format!("{}/login", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -160,7 +268,7 @@ impl TestApp {
 
     pub async fn get_admin_dashboard(&self) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/admin/dashboard", &self.address))
+            .get(format!("{}/admin/dashboard", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -172,7 +280,7 @@ impl TestApp {
 
     pub async fn get_change_password(&self) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/admin/password", &self.address))
+            .get(format!("{}/admin/password", &self.address))
             .send()
             .await
             .expect("Failed to execute request")
@@ -183,7 +291,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.api_client
-            .post(&format!("{}/admin/password", &self.address))
+            .post(format!("{}/admin/password", &self.address))
             .form(body)
             .send()
             .await
@@ -196,7 +304,7 @@ impl TestApp {
 
     pub async fn post_logout(&self) -> reqwest::Response {
         self.api_client
-            .post(&format!("{}/admin/logout", &self.address))
+            .post(format!("{}/admin/logout", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -204,7 +312,7 @@ impl TestApp {
 
     pub async fn get_publish_newsletter(&self) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/admin/newsletter", &self.address))
+            .get(format!("{}/admin/newsletter", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -219,12 +327,258 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.api_client
-            .post(&format!("{}/admin/newsletter", &self.address))
+            .post(format!("{}/admin/newsletter", &self.address))
             .form(body)
             .send()
             .await
             .expect("Failed to execute request")
     }
+
+    pub async fn get_newsletter_issue_status(
+        &self,
+        newsletter_issue_id: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .get(format!(
+                "{}/admin/newsletter/{}/status",
+                &self.address, newsletter_issue_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_newsletter_issue_status_html(&self, newsletter_issue_id: &str) -> String {
+        self.get_newsletter_issue_status(newsletter_issue_id)
+            .await
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_send_test_issue(&self, newsletter_issue_id: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!(
+                "{}/admin/newsletter/{}/test",
+                &self.address, newsletter_issue_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_email_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/email", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_admin_email<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/email", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_archive(&self, page: Option<i64>) -> reqwest::Response {
+        let query = page
+            .map(|page| format!("?page={}", page))
+            .unwrap_or_default();
+        self.api_client
+            .get(format!("{}/issues{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_archive_html(&self, page: Option<i64>) -> String {
+        self.get_archive(page).await.text().await.unwrap()
+    }
+
+    pub async fn get_archived_issue(&self, slug: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/issues/{}", &self.address, slug))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_feed(&self, feed: &str, headers: &[(&str, &str)]) -> reqwest::Response {
+        let mut request = self
+            .api_client
+            .get(format!("{}/feed.{}", &self.address, feed));
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_drafts(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/drafts", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_drafts_html(&self) -> String {
+        self.get_drafts().await.text().await.unwrap()
+    }
+
+    pub async fn post_create_draft<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/drafts", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_edit_draft<Body>(&self, draft_id: &str, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/drafts/{}", &self.address, draft_id))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_draft_preview(&self, draft_id: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!(
+                "{}/admin/drafts/{}/preview",
+                &self.address, draft_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_delete_draft(&self, draft_id: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!(
+                "{}/admin/drafts/{}/delete",
+                &self.address, draft_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_subscribers(&self, query: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/subscribers{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_subscribers_html(&self, query: &str) -> String {
+        self.get_subscribers(query).await.text().await.unwrap()
+    }
+
+    pub async fn get_subscribers_export(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/subscribers/export", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_subscribers_import(&self, csv: &str, status: &str) -> reqwest::Response {
+        let file = reqwest::multipart::Part::text(csv.to_string())
+            .file_name("subscribers.csv")
+            .mime_str("text/csv")
+            .unwrap();
+        let form = reqwest::multipart::Form::new()
+            .text("status", status.to_string())
+            .part("file", file);
+        self.api_client
+            .post(format!("{}/admin/subscribers/import", &self.address))
+            .multipart(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_subscriber_action(
+        &self,
+        subscriber_id: &Uuid,
+        action: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(format!(
+                "{}/admin/subscribers/{}/{}",
+                &self.address, subscriber_id, action
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_suppressions(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/suppressions", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_suppressions_html(&self) -> String {
+        self.get_suppressions().await.text().await.unwrap()
+    }
+
+    pub async fn post_add_suppression<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/suppressions", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_remove_suppression(&self, email: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/admin/suppressions/delete", &self.address))
+            .form(&serde_json::json!({ "email": email }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_publish_draft<Body>(&self, draft_id: &str, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!(
+                "{}/admin/drafts/{}/publish",
+                &self.address, draft_id
+            ))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
 }
 
 // Spin up an instance of our application
@@ -240,6 +594,9 @@ pub async fn spawn_app() -> TestApp {
         let mut c = get_configuration().expect("Failed to read configuration.");
         c.database.database_name = Uuid::new_v4().to_string();
         c.application.port = 0;
+        // requests carry the client address the way the load balancer would add it
+        c.application.trusted_proxy_hops = 1;
+        c.email_client.transport = EmailTransportKind::Postmark;
         c.email_client.base_url = email_server.uri();
         c
     };
@@ -247,7 +604,8 @@ pub async fn spawn_app() -> TestApp {
     // Create and migrate the database
     configure_database(&configuration.database).await;
 
-    let application = Application::build(configuration.clone())
+    let email_client = configuration.email_client.clone().client();
+    let application = Application::build(configuration.clone(), email_client.clone())
         .await
         .expect("Failed to build application.");
     let application_port = application.port();
@@ -266,7 +624,12 @@ pub async fn spawn_app() -> TestApp {
         email_server,
         test_user: TestUser::generate(),
         api_client: client,
-        email_client: configuration.email_client.client(),
+        email_client,
+        issue_delivery_settings: configuration.issue_delivery,
+        email_outbox_settings: configuration.email_outbox,
+        base_url: ApplicationBaseUrl(configuration.application.base_url),
+        hmac_secret: HmacSecret(configuration.application.hmac_secret.into()),
+        postmark_webhook: configuration.postmark_webhook,
     };
     test_app.test_user.store(&test_app.db_pool).await;
     test_app
@@ -0,0 +1,169 @@
+// tests/api/issues.rs
+
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
+use chrono::{TimeZone, Utc};
+use uuid::Uuid;
+
+// stores an issue straight in the database, published on the given day of January 2026 unless it is a draft
+async fn store_issue(app: &TestApp, title: &str, slug: &str, status: &str, day: u32) {
+    let published_at =
+        (status == "published").then(|| Utc.with_ymd_and_hms(2026, 1, day, 12, 0, 0).unwrap());
+    sqlx::query!(
+        "INSERT INTO newsletter_issues
+            (newsletter_issue_id, title, text_content, html_content, status, published_at, slug)
+        VALUES ($1, $2, 'Newsletter body as plain text', '<p>Hi {{ name }}, welcome to ' || $2 || '</p>', $3, $4, $5)",
+        Uuid::new_v4(),
+        title,
+        status,
+        published_at,
+        (status == "published").then_some(slug),
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store the newsletter issue.");
+}
+
+#[tokio::test]
+async fn the_archive_lists_published_issues_newest_first() {
+    // Arrange
+    let app = spawn_app().await;
+    store_issue(&app, "First issue", "first-issue", "published", 1).await;
+    store_issue(&app, "Second issue", "second-issue", "published", 2).await;
+    store_issue(&app, "Draft issue", "draft-issue", "draft", 3).await;
+
+    // Act
+    let response = app.get_archive(None).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    let second = html_page.find("href=\"/issues/second-issue\"").unwrap();
+    let first = html_page.find("href=\"/issues/first-issue\"").unwrap();
+    assert!(second < first);
+    assert!(html_page.contains("2026-01-02"));
+    assert!(!html_page.contains("Draft issue"));
+    assert!(!html_page.contains("Older issues"));
+}
+
+#[tokio::test]
+async fn the_archive_is_paginated() {
+    // Arrange
+    let app = spawn_app().await;
+    for day in 1..=12 {
+        let title = format!("Issue {}", day);
+        store_issue(&app, &title, &format!("issue-{}", day), "published", day).await;
+    }
+
+    // Act
+    let first_page = app.get_archive_html(None).await;
+    let second_page = app.get_archive_html(Some(2)).await;
+
+    // Assert
+    assert_eq!(first_page.matches("<li>").count(), 10);
+    assert!(first_page.contains("href=\"/issues/issue-12\""));
+    assert!(first_page.contains("href=\"/issues?page=2\""));
+    assert!(!first_page.contains("Newer issues"));
+    assert_eq!(second_page.matches("<li>").count(), 2);
+    assert!(second_page.contains("href=\"/issues/issue-1\""));
+    assert!(second_page.contains("href=\"/issues?page=1\""));
+    assert!(!second_page.contains("Older issues"));
+}
+
+#[tokio::test]
+async fn a_page_number_too_large_to_list_is_rejected_with_a_400() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_archive(Some(i64::MAX)).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn an_archived_issue_is_rendered_without_personal_details() {
+    // Arrange
+    let app = spawn_app().await;
+    store_issue(&app, "First issue", "first-issue", "published", 1).await;
+
+    // Act
+    let response = app.get_archived_issue("first-issue").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("<h2>First issue</h2>"));
+    assert!(html_page.contains("<p>Hi reader, welcome to First issue</p>"));
+    assert!(html_page.contains("Published on 2026-01-01"));
+}
+
+#[tokio::test]
+async fn unknown_and_unpublished_issues_are_a_404() {
+    // Arrange
+    let app = spawn_app().await;
+    store_issue(&app, "Draft issue", "draft-issue", "draft", 1).await;
+
+    // Act
+    let unknown = app.get_archived_issue("no-such-issue").await;
+    let draft = app.get_archived_issue("draft-issue").await;
+
+    // Assert
+    assert_eq!(unknown.status().as_u16(), 404);
+    assert_eq!(draft.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn published_issues_get_a_slug_and_an_archive_page() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Rust 1.80 is out!",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+
+    // Assert
+    let issue = sqlx::query!(r#"SELECT slug as "slug!" FROM newsletter_issues"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch the published issue");
+    assert!(issue.slug.starts_with("rust-1-80-is-out-"));
+    assert!(app
+        .get_archive_html(None)
+        .await
+        .contains(&format!("href=\"/issues/{}\"", issue.slug)));
+    let html_page = app
+        .get_archived_issue(&issue.slug)
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("<p>Newsletter body as HTML</p>"));
+}
+
+#[tokio::test]
+async fn the_home_page_links_to_the_archive() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let html_page = app
+        .api_client
+        .get(&app.address)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .text()
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(html_page.contains("href=\"/issues\""));
+}
@@ -1,10 +1,18 @@
 // tests/api/main.rs
 
 mod admin_dashboard;
+mod admin_subscribers;
 mod change_password;
+mod drafts;
+mod feeds;
 mod health_check;
 mod helpers;
+mod issues;
 mod login;
 mod newsletter;
+mod postmark_webhook;
 mod subscriptions;
 mod subscriptions_confirm;
+mod subscriptions_resend;
+mod subscriptions_unsubscribe;
+mod suppressions;
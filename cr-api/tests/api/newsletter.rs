@@ -1,6 +1,8 @@
 // tests/api/newsletter.rs
 
 use crate::helpers::{assert_is_redirect_to, spawn_app, ConfirmationLinks, TestApp};
+use cr_api::configuration::IssueDeliverySettings;
+use cr_api::issue_delivery_worker::{try_execute_task, ExecutionOutcome};
 use fake::faker::internet::en::SafeEmail;
 use fake::faker::name::en::Name;
 use fake::Fake;
@@ -28,6 +30,7 @@ async fn create_unconfirmed_subscriber(app: &TestApp) -> ConfirmationLinks {
         .await
         .error_for_status()
         .unwrap();
+    app.dispatch_all_outbox_emails().await;
 
     // We now inspect the requests received by the mock Postmark server
     // to retrieve the confirmation link and return it
@@ -45,11 +48,19 @@ async fn create_confirmed_subscriber(app: &TestApp) {
     // We can then reuse the same helper and just add
     // an extra step to actually call the confirmation link!
     let confirmation_link = create_unconfirmed_subscriber(app).await;
+    let _mock_guard = Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .named("Welcome confirmed subscriber")
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
     reqwest::get(confirmation_link.html)
         .await
         .unwrap()
         .error_for_status()
         .unwrap();
+    app.dispatch_all_outbox_emails().await;
 }
 
 #[tokio::test]
@@ -346,3 +357,1045 @@ async fn old_idempotency_entries_are_cleaned_up() {
 
     assert_eq!(1, count.value.unwrap());
 }
+
+#[tokio::test]
+async fn failed_deliveries_are_rescheduled_for_a_later_attempt() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let task = sqlx::query!(
+        r#"
+        SELECT n_attempts, next_attempt_at > now() as "is_delayed!"
+        FROM issue_delivery_queue
+        "#
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch the rescheduled delivery task");
+    assert_eq!(task.n_attempts, 1);
+    assert!(task.is_delayed);
+
+    let failures = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM issue_delivery_failures"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count the failed delivery tasks");
+    assert_eq!(failures.value, 0);
+}
+
+#[tokio::test]
+async fn deliveries_exceeding_the_retry_limit_are_moved_to_the_dead_letter_table() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let max_attempts = app.issue_delivery_settings.max_retries as u64 + 1;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(max_attempts)
+        .mount(&app.email_server)
+        .await;
+
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+
+    // Act - make every rescheduled task due straight away and try again
+    for _ in 0..max_attempts {
+        sqlx::query!("UPDATE issue_delivery_queue SET next_attempt_at = now()")
+            .execute(&app.db_pool)
+            .await
+            .expect("Failed to make the delivery task due");
+        app.dispatch_all_pending_emails().await;
+    }
+
+    // Assert
+    let queued = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM issue_delivery_queue"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count the queued delivery tasks");
+    assert_eq!(queued.value, 0);
+
+    let failure = sqlx::query!("SELECT n_attempts, last_error FROM issue_delivery_failures")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch the failed delivery task");
+    assert_eq!(failure.n_attempts as u64, max_attempts);
+    assert!(failure.last_error.contains("500"));
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_see_the_delivery_status_of_an_issue() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .get_newsletter_issue_status(&uuid::Uuid::new_v4().to_string())
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn delivery_status_of_an_unknown_issue_is_a_404() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .get_newsletter_issue_status(&uuid::Uuid::new_v4().to_string())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn delivery_status_reports_pending_and_sent_deliveries() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+    let issue = sqlx::query!("SELECT newsletter_issue_id FROM newsletter_issues")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch the published issue");
+    let issue_id = issue.newsletter_issue_id.to_string();
+
+    // Act - Part 1 - Check the status before delivery
+    let html_page = app.get_newsletter_issue_status_html(&issue_id).await;
+    assert!(html_page.contains("Pending: 1"));
+    assert!(html_page.contains("Sent: 0"));
+
+    // Act - Part 2 - Check the status after delivery
+    app.dispatch_all_pending_emails().await;
+    let html_page = app.get_newsletter_issue_status_html(&issue_id).await;
+    assert!(html_page.contains("Pending: 0"));
+    assert!(html_page.contains("Sent: 1"));
+
+    let delivery = sqlx::query!("SELECT status, error FROM newsletter_issue_deliveries")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch the logged delivery");
+    assert_eq!(delivery.status, "sent");
+    assert!(delivery.error.is_none());
+}
+
+#[tokio::test]
+async fn newsletter_issues_carry_a_working_unsubscribe_link() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+    app.dispatch_all_pending_emails().await;
+
+    // Act - Part 1 - Inspect the delivered issue
+    let email_request = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    let headers = body["Headers"].as_array().unwrap();
+    let list_unsubscribe = headers
+        .iter()
+        .find(|h| h["Name"] == "List-Unsubscribe")
+        .unwrap()["Value"]
+        .as_str()
+        .unwrap();
+    assert!(headers.iter().any(
+        |h| h["Name"] == "List-Unsubscribe-Post" && h["Value"] == "List-Unsubscribe=One-Click"
+    ));
+    let unsubscribe_link = list_unsubscribe
+        .trim_start_matches('<')
+        .trim_end_matches('>');
+    assert!(body["HtmlBody"]
+        .as_str()
+        .unwrap()
+        .contains(unsubscribe_link));
+    assert!(body["TextBody"]
+        .as_str()
+        .unwrap()
+        .contains(unsubscribe_link));
+
+    // Act - Part 2 - Use the link the way a mail client would
+    let mut unsubscribe_link = reqwest::Url::parse(unsubscribe_link).unwrap();
+    assert_eq!(unsubscribe_link.host_str().unwrap(), "127.0.0.1");
+    unsubscribe_link.set_port(Some(app.port)).unwrap();
+    let response = reqwest::Client::new()
+        .post(unsubscribe_link)
+        .body("List-Unsubscribe=One-Click")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Assert
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved subscription.");
+    assert_eq!(saved.status, "unsubscribed");
+}
+
+#[tokio::test]
+async fn scheduled_issues_are_not_delivered_before_they_are_due() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - Part 1 - Submit newsletter form with a publishing time in the future
+    let scheduled_for = (chrono::Utc::now() + chrono::Duration::days(1))
+        .format("%Y-%m-%dT%H:%M")
+        .to_string();
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "scheduled_for": scheduled_for,
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("The newsletter issue has been scheduled"));
+
+    // Act - Part 3 - Run the workers
+    app.publish_scheduled_issues().await;
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let issue = sqlx::query!("SELECT status, published_at FROM newsletter_issues")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch the scheduled issue");
+    assert_eq!(issue.status, "scheduled");
+    assert!(issue.published_at.is_none());
+    // Mock verifies on Drop that we haven't sent the newsletter email
+}
+
+#[tokio::test]
+async fn a_retried_schedule_request_replays_the_original_response() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let scheduled_for = (chrono::Utc::now() + chrono::Duration::days(1))
+        .format("%Y-%m-%dT%H:%M")
+        .to_string();
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "scheduled_for": scheduled_for,
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await;
+    app.get_publish_newsletter_html().await;
+
+    // Act
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+
+    // Assert
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("The newsletter issue has been scheduled"));
+    assert!(!html_page.contains("The newsletter issue has been accepted"));
+    let issues = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM newsletter_issues"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count the newsletter issues");
+    assert_eq!(issues.value, 1);
+}
+
+#[tokio::test]
+async fn due_scheduled_issues_are_published_and_delivered_once() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let scheduled_for = (chrono::Utc::now() + chrono::Duration::days(1))
+        .format("%Y-%m-%dT%H:%M")
+        .to_string();
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "scheduled_for": scheduled_for,
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+
+    // Act - make the issue due and run the scheduling worker more than once
+    sqlx::query!("UPDATE newsletter_issues SET scheduled_for = now() - interval '1 minute'")
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to make the scheduled issue due");
+    app.publish_scheduled_issues().await;
+    app.publish_scheduled_issues().await;
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let issue = sqlx::query!("SELECT status, published_at, slug FROM newsletter_issues")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch the published issue");
+    assert_eq!(issue.status, "published");
+    assert!(issue.published_at.is_some());
+    assert!(issue.slug.is_some());
+    // Mock verifies on Drop that we have sent the newsletter email **once**
+}
+
+#[tokio::test]
+async fn scheduling_an_issue_in_the_past_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "scheduled_for": "2020-01-01T12:00",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+
+    // Assert
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("The publishing time must be in the future."));
+    let issues = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM newsletter_issues"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count the newsletter issues");
+    assert_eq!(issues.value, 0);
+}
+
+#[tokio::test]
+async fn markdown_issues_are_rendered_to_html_and_plain_text() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let markdown =
+        "# Big news\n\nRead the [announcement](https://example.com).<script>alert(1)</script>";
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "markdown_content": markdown,
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let issue =
+        sqlx::query!("SELECT text_content, html_content, markdown_content FROM newsletter_issues")
+            .fetch_one(&app.db_pool)
+            .await
+            .expect("Failed to fetch the newsletter issue");
+    assert_eq!(issue.markdown_content.as_deref(), Some(markdown));
+    assert!(issue.html_content.contains("<h1>Big news</h1>"));
+    assert!(!issue.html_content.contains("<script>"));
+    assert!(issue
+        .text_content
+        .contains("Read the announcement (https://example.com)."));
+
+    let email_request = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    assert!(body["HtmlBody"]
+        .as_str()
+        .unwrap()
+        .contains("<h1>Big news</h1>"));
+    let text_body = body["TextBody"].as_str().unwrap();
+    assert!(text_body.starts_with("Crusty Rustacean - The Newsletter"));
+    assert!(text_body.contains("\n\nBig news\n"));
+}
+
+#[tokio::test]
+async fn an_issue_needs_either_markdown_or_both_html_and_plain_text_content() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act - Part 1 - Blank Markdown content
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "markdown_content": "   ",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("The Markdown content of a newsletter issue can&#x27;t be empty."));
+
+    // Act - Part 2 - No Markdown content and no plain text content
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "markdown_content": "",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+
+    // Assert
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("Part of the form body has less than 5 characters"));
+}
+
+async fn create_draft_issue(app: &TestApp) -> uuid::Uuid {
+    let newsletter_issue_id = uuid::Uuid::new_v4();
+    sqlx::query!(
+        "INSERT INTO newsletter_issues (newsletter_issue_id, title, text_content, html_content, status)
+        VALUES ($1, 'Newsletter title', 'Newsletter body as plain text', '<p>Newsletter body as HTML</p>', 'draft')",
+        newsletter_issue_id,
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store the draft issue.");
+    newsletter_issue_id
+}
+
+#[tokio::test]
+async fn test_emails_are_only_sent_to_the_logged_in_admin() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = create_draft_issue(&app).await;
+
+    let response = app
+        .post_admin_email(&serde_json::json!({ "email": "admin@example.com" }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/email");
+    assert!(app
+        .get_admin_email_html()
+        .await
+        .contains("Your email address has been changed."));
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_send_test_issue(&newsletter_issue_id.to_string())
+        .await;
+
+    // Assert
+    assert_is_redirect_to(
+        &response,
+        &format!("/admin/drafts/{}/preview", newsletter_issue_id),
+    );
+    let email_request = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    assert_eq!(body["To"], "admin@example.com");
+    assert!(body["HtmlBody"]
+        .as_str()
+        .unwrap()
+        .contains("<p>Newsletter body as HTML</p>"));
+    assert!(body["HtmlBody"]
+        .as_str()
+        .unwrap()
+        .contains("/subscriptions/unsubscribe?token="));
+
+    let queued = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM issue_delivery_queue"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count the queued deliveries");
+    assert_eq!(queued.value, 0);
+}
+
+#[tokio::test]
+async fn sending_a_test_email_requires_an_admin_email_address() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_issue_id = create_draft_issue(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_send_test_issue(&newsletter_issue_id.to_string())
+        .await;
+
+    // Assert
+    let location = format!("/admin/drafts/{}/preview", newsletter_issue_id);
+    assert_is_redirect_to(&response, &location);
+    let html_page = app
+        .api_client
+        .get(format!("{}{}", app.address, location))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("Set your email address before sending a test email."));
+}
+
+#[tokio::test]
+async fn an_invalid_admin_email_address_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_admin_email(&serde_json::json!({ "email": "not-an-email" }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/email");
+    let user = sqlx::query!(
+        "SELECT email FROM users WHERE user_id = $1",
+        app.test_user.user_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch the test user");
+    assert!(user.email.is_none());
+}
+
+#[tokio::test]
+async fn deliveries_are_sent_in_batches_and_results_are_mapped_back_to_each_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    for email in [
+        "first@example.com",
+        "second@example.com",
+        "third@example.com",
+    ] {
+        sqlx::query!(
+            "INSERT INTO subscriptions (id, email, name, subscribed_at, status)
+            VALUES ($1, $2, 'le guin', now(), 'confirmed')",
+            uuid::Uuid::new_v4(),
+            email,
+        )
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to store test subscriber.");
+    }
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email/batch"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+            { "ErrorCode": 0, "Message": "OK" },
+            { "ErrorCode": 406, "Message": "Inactive recipient" },
+            { "ErrorCode": 0, "Message": "OK" }
+        ])))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    let batch = body.as_array().unwrap();
+    assert_eq!(batch.len(), 3);
+    let rejected_email = batch[1]["To"].as_str().unwrap();
+
+    let sent = sqlx::query!(
+        r#"SELECT COUNT(*) as "value!" FROM newsletter_issue_deliveries WHERE status = 'sent'"#
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to count the sent deliveries");
+    assert_eq!(sent.value, 2);
+    let queued = sqlx::query!("SELECT subscriber_email, n_attempts FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch the rescheduled delivery");
+    assert_eq!(queued.subscriber_email, rejected_email);
+    assert_eq!(queued.n_attempts, 1);
+}
+
+#[tokio::test]
+async fn failing_to_record_one_delivery_does_not_send_the_rest_of_the_batch_again() {
+    // Arrange
+    let app = spawn_app().await;
+    for email in [
+        "first@example.com",
+        "second@example.com",
+        "third@example.com",
+    ] {
+        sqlx::query!(
+            "INSERT INTO subscriptions (id, email, name, subscribed_at, status)
+            VALUES ($1, $2, 'le guin', now(), 'confirmed')",
+            uuid::Uuid::new_v4(),
+            email,
+        )
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to store test subscriber.");
+    }
+    app.test_user.login(&app).await;
+    // Sabotage the delivery log for a single subscriber
+    sqlx::raw_sql(
+        r#"
+        CREATE FUNCTION reject_second_delivery() RETURNS trigger AS $$
+        BEGIN
+            IF NEW.subscriber_email = 'second@example.com' THEN
+                RAISE EXCEPTION 'sabotaged';
+            END IF;
+            RETURN NEW;
+        END
+        $$ LANGUAGE plpgsql;
+        CREATE TRIGGER reject_second_delivery BEFORE INSERT ON newsletter_issue_deliveries
+        FOR EACH ROW EXECUTE FUNCTION reject_second_delivery();
+        "#,
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    Mock::given(path("/email/batch"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+            { "ErrorCode": 0, "Message": "OK" },
+            { "ErrorCode": 0, "Message": "OK" },
+            { "ErrorCode": 0, "Message": "OK" }
+        ])))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+
+    // Act - Part 1 - Recording the second delivery fails
+    let outcome = try_execute_task(
+        &app.db_pool,
+        &app.email_client,
+        &app.issue_delivery_settings,
+        &app.base_url,
+        &app.hmac_secret,
+    )
+    .await;
+    assert!(outcome.is_err());
+    let queued = sqlx::query!("SELECT subscriber_email FROM issue_delivery_queue")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch the queued deliveries");
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].subscriber_email, "second@example.com");
+
+    // Act - Part 2 - Only that delivery is picked up again once its claim has elapsed
+    sqlx::raw_sql("DROP TRIGGER reject_second_delivery ON newsletter_issue_deliveries")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    sqlx::query!("UPDATE issue_delivery_queue SET next_attempt_at = now()")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let sent = sqlx::query!(
+        r#"SELECT COUNT(*) as "value!" FROM newsletter_issue_deliveries WHERE status = 'sent'"#
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to count the sent deliveries");
+    assert_eq!(sent.value, 3);
+    // Mock verifies on Drop that the batch went out once and the lone delivery once more
+}
+
+#[tokio::test]
+async fn a_chunk_which_cannot_be_prepared_is_released_straight_away() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+    // Sabotage loading the issue
+    sqlx::raw_sql("ALTER TABLE newsletter_issues RENAME COLUMN title TO sabotaged_title")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act - Part 1 - The issue can't be loaded
+    let outcome = try_execute_task(
+        &app.db_pool,
+        &app.email_client,
+        &app.issue_delivery_settings,
+        &app.base_url,
+        &app.hmac_secret,
+    )
+    .await;
+    assert!(outcome.is_err());
+    let due = sqlx::query!(
+        r#"SELECT COUNT(*) as "value!" FROM issue_delivery_queue WHERE next_attempt_at <= now()"#
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to count the due deliveries");
+    assert_eq!(due.value, 1);
+
+    // Act - Part 2 - The delivery goes out without waiting for the claim to elapse
+    sqlx::raw_sql("ALTER TABLE newsletter_issues RENAME COLUMN sabotaged_title TO title")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+    app.dispatch_all_pending_emails().await;
+    // Mock verifies on Drop that the delivery went out
+}
+
+#[tokio::test]
+async fn concurrent_delivery_workers_send_each_delivery_once() {
+    // Arrange
+    let app = spawn_app().await;
+    for _ in 0..3 {
+        create_confirmed_subscriber(&app).await;
+    }
+    app.test_user.login(&app).await;
+
+    // a slow email server keeps every worker busy with its own task
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+        .expect(3)
+        .mount(&app.email_server)
+        .await;
+
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+
+    // Act
+    let settings = IssueDeliverySettings {
+        batch_size: 1,
+        ..app.issue_delivery_settings.clone()
+    };
+    let worker = || async {
+        while let ExecutionOutcome::TaskCompleted = try_execute_task(
+            &app.db_pool,
+            &app.email_client,
+            &settings,
+            &app.base_url,
+            &app.hmac_secret,
+        )
+        .await
+        .unwrap()
+        {}
+    };
+    tokio::join!(worker(), worker(), worker());
+
+    // Assert
+    let sent = sqlx::query!(
+        r#"SELECT COUNT(*) as "value!" FROM newsletter_issue_deliveries WHERE status = 'sent'"#
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to count the sent deliveries");
+    assert_eq!(sent.value, 3);
+    // Mock verifies on Drop that every subscriber got the newsletter exactly once
+}
+
+#[tokio::test]
+async fn placeholders_are_expanded_for_each_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    sqlx::query!(
+        "INSERT INTO subscriptions (id, email, name, subscribed_at, status)
+        VALUES ($1, 'ursula_le_guin@gmail.com', 'Ursula & co', now(), 'confirmed')",
+        uuid::Uuid::new_v4(),
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store test subscriber.");
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Hi {{ name }}, this went to {{email}}. Leave at {{ unsubscribe_url }}",
+        "html_content": "<p>Hi {{ name }}</p><a href=\"{{ unsubscribe_url }}\">Leave</a>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    let html_body = body["HtmlBody"].as_str().unwrap();
+    let text_body = body["TextBody"].as_str().unwrap();
+    assert!(html_body.contains("<p>Hi Ursula &amp; co</p>"));
+    assert!(text_body.contains("Hi Ursula & co, this went to ursula_le_guin@gmail.com."));
+    assert!(!html_body.contains("{{"));
+    assert!(!text_body.contains("{{"));
+    let list_unsubscribe = body["Headers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|h| h["Name"] == "List-Unsubscribe")
+        .unwrap()["Value"]
+        .as_str()
+        .unwrap();
+    let unsubscribe_link = list_unsubscribe
+        .trim_start_matches('<')
+        .trim_end_matches('>');
+    assert!(text_body.contains(&format!("Leave at {}", unsubscribe_link)));
+}
+
+#[tokio::test]
+async fn issues_with_unknown_placeholders_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Hi {{ first_name }}",
+        "html_content": "<p>Hi {{ name }}</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+
+    // Assert
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("Unknown placeholder(s): {{ first_name }}."));
+    let issues = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM newsletter_issues"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count the newsletter issues");
+    assert_eq!(issues.value, 0);
+}
+
+#[tokio::test]
+async fn braces_in_code_samples_are_sent_as_they_are_written() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Hi {{ name }}, try println!(\"\\{{name}} \\{{x}}\");",
+        "html_content": "<p>Hi {{ name }}, try</p><pre><code>println!(\"{{name}} {{x}}\");</code></pre>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let email_request = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    let html_body = body["HtmlBody"].as_str().unwrap();
+    let text_body = body["TextBody"].as_str().unwrap();
+    assert!(html_body.contains("<pre><code>println!(\"{{name}} {{x}}\");</code></pre>"));
+    assert!(text_body.contains("try println!(\"{{name}} {{x}}\");"));
+    assert!(!text_body.contains("Hi {{ name }}"));
+}
+
+#[tokio::test]
+async fn published_issues_are_timestamped() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let before = chrono::Utc::now();
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+
+    // Assert
+    let issue = sqlx::query!(
+        r#"SELECT published_at as "published_at!", created_at, updated_at FROM newsletter_issues"#
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch the published issue");
+    assert!(issue.published_at >= before && issue.published_at <= chrono::Utc::now());
+    assert_eq!(issue.created_at, issue.published_at);
+    assert_eq!(issue.updated_at, issue.published_at);
+}
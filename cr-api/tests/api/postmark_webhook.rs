@@ -0,0 +1,274 @@
+// tests/api/postmark_webhook.rs
+
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const SUBSCRIBER_EMAIL: &str = "ursula_le_guin@gmail.com";
+
+async fn create_confirmed_subscriber(app: &TestApp) {
+    sqlx::query!(
+        "INSERT INTO subscriptions (id, email, name, subscribed_at, status)
+        VALUES ($1, $2, 'le guin', now(), 'confirmed')",
+        Uuid::new_v4(),
+        SUBSCRIBER_EMAIL,
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store test subscriber.");
+}
+
+async fn subscriber_status(app: &TestApp) -> String {
+    sqlx::query!(
+        "SELECT status FROM subscriptions WHERE email = $1",
+        SUBSCRIBER_EMAIL
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch the subscriber status.")
+    .status
+}
+
+async fn recorded_events(app: &TestApp) -> Vec<(String, String)> {
+    sqlx::query!("SELECT event_type, detail FROM subscriber_events ORDER BY received_at")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch the subscriber events.")
+        .into_iter()
+        .map(|r| (r.event_type, r.detail))
+        .collect()
+}
+
+fn bounce(id: i64, bounce_type: &str) -> serde_json::Value {
+    serde_json::json!({
+        "RecordType": "Bounce",
+        "ID": id,
+        "Type": bounce_type,
+        "TypeCode": 1,
+        "Name": "Hard bounce",
+        "MessageStream": "outbound",
+        "Description": "The server was unable to deliver your message.",
+        "Email": SUBSCRIBER_EMAIL,
+        "BouncedAt": "2026-10-18T16:00:00Z",
+    })
+}
+
+fn spam_complaint(id: i64) -> serde_json::Value {
+    serde_json::json!({
+        "RecordType": "SpamComplaint",
+        "ID": id,
+        "Type": "SpamComplaint",
+        "TypeCode": 512,
+        "MessageStream": "outbound",
+        "Email": SUBSCRIBER_EMAIL,
+        "BouncedAt": "2026-10-18T16:00:00Z",
+    })
+}
+
+#[tokio::test]
+async fn webhook_requests_without_valid_credentials_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+
+    // Act
+    let no_credentials = app
+        .api_client
+        .post(format!("{}/webhooks/postmark", &app.address))
+        .json(&bounce(1, "HardBounce"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let wrong_password = app
+        .post_postmark_webhook_as(
+            &app.postmark_webhook.username,
+            "not-the-secret",
+            &bounce(1, "HardBounce"),
+        )
+        .await;
+
+    // Assert
+    for response in [no_credentials, wrong_password] {
+        assert_eq!(response.status().as_u16(), 401);
+        assert_eq!(
+            r#"Basic realm="webhooks""#,
+            response.headers()["WWW-Authenticate"]
+        );
+    }
+    assert_eq!(subscriber_status(&app).await, "confirmed");
+    assert!(recorded_events(&app).await.is_empty());
+}
+
+#[tokio::test]
+async fn a_hard_bounce_marks_the_subscriber_as_bounced() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+
+    // Act
+    let response = app.post_postmark_webhook(&bounce(1, "HardBounce")).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "bounced");
+    let suppression = sqlx::query!(
+        "SELECT reason, source FROM suppressions WHERE email = $1",
+        SUBSCRIBER_EMAIL
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch the suppression.");
+    assert_eq!(suppression.reason, "bounced");
+    assert_eq!(suppression.source, "postmark");
+    assert_eq!(
+        recorded_events(&app).await,
+        vec![("bounce".to_string(), "HardBounce".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn a_bounce_matches_the_subscriber_whatever_the_case_of_the_address() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let mut event = bounce(1, "HardBounce");
+    event["Email"] = SUBSCRIBER_EMAIL.to_uppercase().into();
+
+    // Act
+    let response = app.post_postmark_webhook(&event).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "bounced");
+    let event = sqlx::query!(
+        r#"
+        SELECT subscriber_events.email
+        FROM subscriber_events JOIN subscriptions ON subscriptions.id = subscriber_events.subscriber_id
+        WHERE subscriptions.email = $1
+        "#,
+        SUBSCRIBER_EMAIL
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch the event stored for the subscriber.");
+    assert_eq!(event.email, SUBSCRIBER_EMAIL.to_uppercase());
+}
+
+#[tokio::test]
+async fn a_spam_complaint_marks_the_subscriber_as_complained() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+
+    // Act
+    let response = app.post_postmark_webhook(&spam_complaint(1)).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "complained");
+    assert_eq!(
+        recorded_events(&app).await,
+        vec![("spam_complaint".to_string(), "SpamComplaint".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn a_soft_bounce_is_recorded_without_changing_the_subscription() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+
+    // Act
+    let response = app.post_postmark_webhook(&bounce(1, "SoftBounce")).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "confirmed");
+    assert_eq!(recorded_events(&app).await.len(), 1);
+}
+
+#[tokio::test]
+async fn a_redelivered_event_is_recorded_once() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+
+    // Act
+    app.post_postmark_webhook(&spam_complaint(1)).await;
+    let response = app.post_postmark_webhook(&spam_complaint(1)).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(recorded_events(&app).await.len(), 1);
+}
+
+#[tokio::test]
+async fn other_record_types_are_acknowledged_and_ignored() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+
+    // Act
+    let response = app
+        .post_postmark_webhook(&serde_json::json!({
+            "RecordType": "Open",
+            "ID": 1,
+            "Email": SUBSCRIBER_EMAIL,
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "confirmed");
+    assert!(recorded_events(&app).await.is_empty());
+}
+
+#[tokio::test]
+async fn a_malformed_payload_is_rejected_with_a_400() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_postmark_webhook(&serde_json::json!({ "RecordType": "Bounce" }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn bounced_subscribers_no_longer_receive_newsletters() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.post_postmark_webhook(&bounce(1, "HardBounce")).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let queued = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM issue_delivery_queue"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count the queued deliveries");
+    assert_eq!(queued.value, 0);
+    // Mock verifies on Drop that we haven't sent the newsletter email
+}
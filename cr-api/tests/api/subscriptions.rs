@@ -1,6 +1,7 @@
 // tests/api/subscriptions.rs
 
-use crate::helpers::spawn_app;
+use crate::helpers::{spawn_app, TestApp};
+use cr_api::email_outbox_worker::try_send_outbox_emails;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
 
@@ -113,6 +114,7 @@ async fn subscribe_sends_a_confirmation_email_for_valid_data() {
 
     // Act
     app.post_subscriptions(body.into()).await;
+    app.dispatch_all_outbox_emails().await;
 
     // Assert
     // Mock asserts on drop
@@ -132,6 +134,7 @@ async fn subscribe_sends_a_confirmation_email_with_a_link() {
 
     // Act
     app.post_subscriptions(body.into()).await;
+    app.dispatch_all_outbox_emails().await;
 
     // Assert
     let email_request = &app.email_server.received_requests().await.unwrap()[0];
@@ -158,3 +161,329 @@ async fn subscribe_fails_if_there_is_a_fatal_database_error() {
     // Assert
     assert_eq!(response.status().as_u16(), 500);
 }
+
+async fn subscriber_status(app: &TestApp) -> String {
+    sqlx::query!("SELECT status FROM subscriptions WHERE email = 'ursula_le_guin@gmail.com'")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved subscription.")
+        .status
+}
+
+#[tokio::test]
+async fn subscribing_again_while_pending_sends_a_fresh_confirmation_link() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let first_response = app.post_subscriptions(body.into()).await;
+    let second_response = app.post_subscriptions(body.into()).await;
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    assert_eq!(first_response.status().as_u16(), 200);
+    assert_eq!(second_response.status().as_u16(), 200);
+    let email_requests = app.email_server.received_requests().await.unwrap();
+    let first_link = app.get_confirmation_links(&email_requests[0]).html;
+    let second_link = app.get_confirmation_links(&email_requests[1]).html;
+    assert_ne!(first_link, second_link);
+    // the fresh link confirms the subscription
+    reqwest::get(second_link)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+    assert_eq!(subscriber_status(&app).await, "confirmed");
+}
+
+#[tokio::test]
+async fn subscribing_again_once_confirmed_sends_an_already_subscribed_notice() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(3)
+        .mount(&app.email_server)
+        .await;
+
+    let first_response = app.post_subscriptions(body.into()).await;
+    let first_status = first_response.status().as_u16();
+    let first_page = first_response.text().await.unwrap();
+    app.dispatch_all_outbox_emails().await;
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_link = app.get_confirmation_links(email_request).html;
+    reqwest::get(confirmation_link)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+    // the welcome email goes out
+    app.dispatch_all_outbox_emails().await;
+
+    // Act
+    let second_response = app.post_subscriptions(body.into()).await;
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    // the response doesn't tell the two cases apart
+    assert_eq!(second_response.status().as_u16(), first_status);
+    assert_eq!(second_response.text().await.unwrap(), first_page);
+    let email_request = &app.email_server.received_requests().await.unwrap()[2];
+    let email_body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    assert_eq!(email_body["Subject"], "You're already subscribed");
+    assert!(email_body["TextBody"]
+        .as_str()
+        .unwrap()
+        .contains("/subscriptions/unsubscribe?token="));
+    assert_eq!(subscriber_status(&app).await, "confirmed");
+}
+
+#[tokio::test]
+async fn repeat_signups_are_throttled_per_email_address() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(4)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let first_response = app.post_subscriptions(body.into()).await;
+    let first_page = first_response.text().await.unwrap();
+    let mut repeat_responses = Vec::new();
+    for _ in 0..5 {
+        repeat_responses.push(app.post_subscriptions(body.into()).await);
+    }
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    // the response doesn't tell a throttled signup apart either
+    for response in repeat_responses {
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(response.text().await.unwrap(), first_page);
+    }
+    // Mock verifies on Drop that only the first signup and three repeats were emailed
+}
+
+#[tokio::test]
+async fn concurrent_signups_for_a_new_address_all_succeed() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+    // Act
+    let responses =
+        futures_util::future::join_all((0..4).map(|_| app.post_subscriptions(body.into()))).await;
+
+    // Assert
+    for response in responses {
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    let saved = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM subscriptions"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count the subscriptions.");
+    assert_eq!(saved.value, 1);
+}
+
+#[tokio::test]
+async fn an_unsubscribed_address_can_subscribe_again() {
+    // Arrange - an unsubscribed address whose suppression has since been lifted
+    let app = spawn_app().await;
+    sqlx::query!(
+        "INSERT INTO subscriptions (id, email, name, subscribed_at, status)
+        VALUES ($1, 'ursula_le_guin@gmail.com', 'le guin', now(), 'unsubscribed')",
+        uuid::Uuid::new_v4(),
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store test subscriber.");
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com".into())
+        .await;
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "pending_confirmation");
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_link = app.get_confirmation_links(email_request).html;
+    assert!(confirmation_link
+        .as_str()
+        .contains("/subscriptions/confirm?subscription_token="));
+}
+
+#[tokio::test]
+async fn subscribe_succeeds_while_the_email_provider_is_down_and_the_email_is_retried() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - Part 1 - The first attempt fails, but the subscription goes through
+    let response = app.post_subscriptions(body.into()).await;
+    assert_eq!(response.status().as_u16(), 200);
+    app.dispatch_all_outbox_emails().await;
+    let queued = sqlx::query!(
+        r#"SELECT n_attempts, next_attempt_at > now() as "rescheduled!" FROM email_outbox"#
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch the outbox email.");
+    assert_eq!(queued.n_attempts, 1);
+    assert!(queued.rescheduled);
+
+    // Act - Part 2 - The retry goes out once it is due
+    sqlx::query!("UPDATE email_outbox SET next_attempt_at = now()")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    let remaining = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM email_outbox"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count the outbox emails.");
+    assert_eq!(remaining.value, 0);
+    assert_eq!(subscriber_status(&app).await, "pending_confirmation");
+}
+
+#[tokio::test]
+async fn a_confirmation_email_is_not_sent_once_its_recipient_is_suppressed() {
+    // Arrange
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com".into())
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    sqlx::query!(
+        "INSERT INTO suppressions (email, reason, source, created_at)
+        VALUES ('ursula_le_guin@gmail.com', 'Bounced', 'postmark', now())"
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to suppress the subscriber.");
+
+    // Act
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    let remaining = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM email_outbox"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count the outbox emails.");
+    assert_eq!(remaining.value, 0);
+    // Mock verifies on Drop that nothing went out
+}
+
+#[tokio::test]
+async fn failing_to_record_one_outbox_email_does_not_send_the_others_again() {
+    // Arrange
+    let app = spawn_app().await;
+    for body in [
+        "name=le%20guin&email=first%40example.com",
+        "name=le%20guin&email=second%40example.com",
+        "name=le%20guin&email=third%40example.com",
+    ] {
+        let response = app.post_subscriptions(body.into()).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    // Sabotage settling the email to a single subscriber
+    sqlx::raw_sql(
+        r#"
+        CREATE FUNCTION reject_second_email() RETURNS trigger AS $$
+        BEGIN
+            IF OLD.recipient = 'second@example.com' THEN
+                RAISE EXCEPTION 'sabotaged';
+            END IF;
+            RETURN OLD;
+        END
+        $$ LANGUAGE plpgsql;
+        CREATE TRIGGER reject_second_email BEFORE DELETE ON email_outbox
+        FOR EACH ROW EXECUTE FUNCTION reject_second_email();
+        "#,
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(4)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - Part 1 - Recording the second email fails
+    let outcome =
+        try_send_outbox_emails(&app.db_pool, &app.email_client, &app.email_outbox_settings).await;
+    assert!(outcome.is_err());
+    let queued = sqlx::query!("SELECT recipient FROM email_outbox")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("Failed to fetch the outbox emails.");
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].recipient, "second@example.com");
+
+    // Act - Part 2 - Only that email is picked up again once its claim has elapsed
+    sqlx::raw_sql("DROP TRIGGER reject_second_email ON email_outbox")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    sqlx::query!("UPDATE email_outbox SET next_attempt_at = now()")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    let remaining = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM email_outbox"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count the outbox emails.");
+    assert_eq!(remaining.value, 0);
+    // Mock verifies on Drop that each email went out once and the second one once more
+}
@@ -31,6 +31,7 @@ async fn the_link_returned_by_subscribe_returns_a_200_if_called() {
         .await;
 
     app.post_subscriptions(body.into()).await;
+    app.dispatch_all_outbox_emails().await;
     let email_request = &app.email_server.received_requests().await.unwrap()[0];
     let confirmation_links = app.get_confirmation_links(email_request);
 
@@ -52,6 +53,7 @@ async fn clicking_on_the_confirmation_link_confirms_a_subscriber() {
         .mount(&app.email_server)
         .await;
     app.post_subscriptions(body.into()).await;
+    app.dispatch_all_outbox_emails().await;
     let email_request = &app.email_server.received_requests().await.unwrap()[0];
     let confirmation_links = app.get_confirmation_links(email_request);
 
@@ -72,3 +74,187 @@ async fn clicking_on_the_confirmation_link_confirms_a_subscriber() {
     assert_eq!(saved.name, "le guin");
     assert_eq!(saved.status, "confirmed");
 }
+
+#[tokio::test]
+async fn a_confirmation_link_can_only_be_used_once() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+    app.post_subscriptions(body.into()).await;
+    app.dispatch_all_outbox_emails().await;
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_links = app.get_confirmation_links(email_request);
+    reqwest::get(confirmation_links.html.clone())
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+
+    // Act
+    let response = reqwest::get(confirmation_links.html).await.unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 410);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("This confirmation link is no longer valid."));
+}
+
+#[tokio::test]
+async fn an_expired_confirmation_link_does_not_confirm_the_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+    app.post_subscriptions(body.into()).await;
+    app.dispatch_all_outbox_emails().await;
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_links = app.get_confirmation_links(email_request);
+    sqlx::query!("UPDATE subscription_tokens SET created_at = now() - interval '3 days'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let response = reqwest::get(confirmation_links.html).await.unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 410);
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved subscription.");
+    assert_eq!(saved.status, "pending_confirmation");
+}
+
+#[tokio::test]
+async fn a_link_sent_before_a_bounce_does_not_confirm_the_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+    app.post_subscriptions(body.into()).await;
+    app.dispatch_all_outbox_emails().await;
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_links = app.get_confirmation_links(email_request);
+    sqlx::query!("UPDATE subscriptions SET status = 'bounced'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let response = reqwest::get(confirmation_links.html).await.unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 410);
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved subscription.");
+    assert_eq!(saved.status, "bounced");
+}
+
+#[tokio::test]
+async fn cleanup_purges_stale_pending_subscribers_and_their_tokens() {
+    // Arrange
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+    for body in [
+        "name=stale&email=stale%40gmail.com",
+        "name=recent&email=recent%40gmail.com",
+        "name=confirmed&email=confirmed%40gmail.com",
+    ] {
+        app.post_subscriptions(body.into()).await;
+    }
+    sqlx::query!(
+        "UPDATE subscriptions SET status = 'confirmed' WHERE email = 'confirmed@gmail.com'"
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "UPDATE subscriptions SET subscribed_at = now() - interval '8 days' \
+        WHERE email IN ('stale@gmail.com', 'confirmed@gmail.com')"
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "UPDATE subscription_tokens SET created_at = now() - interval '8 days' \
+        WHERE subscriber_id IN \
+        (SELECT id FROM subscriptions WHERE email IN ('stale@gmail.com', 'confirmed@gmail.com'))"
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    app.clean_up_stale_subscriptions().await;
+
+    // Assert
+    let remaining: Vec<String> = sqlx::query!("SELECT email FROM subscriptions ORDER BY email")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| r.email)
+        .collect();
+    assert_eq!(remaining, vec!["confirmed@gmail.com", "recent@gmail.com"]);
+    let tokens = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM subscription_tokens"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(tokens.value, 1);
+}
+
+#[tokio::test]
+async fn confirming_a_subscription_sends_a_welcome_email() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+    app.post_subscriptions(body.into()).await;
+    app.dispatch_all_outbox_emails().await;
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_links = app.get_confirmation_links(email_request);
+
+    // Act
+    reqwest::get(confirmation_links.html)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    let email_request = &app.email_server.received_requests().await.unwrap()[1];
+    let email_body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    assert_eq!(email_body["To"], "ursula_le_guin@gmail.com");
+    assert_eq!(email_body["Subject"], "Welcome!");
+    for body in [&email_body["HtmlBody"], &email_body["TextBody"]] {
+        let body = body.as_str().unwrap();
+        assert!(body.contains("Crusty Rustacean - The Newsletter"));
+        assert!(body.contains("/subscriptions/unsubscribe?token="));
+    }
+}
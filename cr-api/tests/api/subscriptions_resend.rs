@@ -0,0 +1,219 @@
+// tests/api/subscriptions_resend.rs
+
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const SUBSCRIBER_EMAIL: &str = "ursula_le_guin@gmail.com";
+const CLIENT_IP: &str = "203.0.113.7";
+
+async fn create_pending_subscriber(app: &TestApp) {
+    let _mock_guard = Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .named("Create pending subscriber")
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+    app.post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com".into())
+        .await
+        .error_for_status()
+        .unwrap();
+    app.dispatch_all_outbox_emails().await;
+}
+
+#[tokio::test]
+async fn resending_sends_a_new_working_confirmation_link() {
+    // Arrange
+    let app = spawn_app().await;
+    create_pending_subscriber(&app).await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_resend_confirmation(SUBSCRIBER_EMAIL, CLIENT_IP)
+        .await;
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let email_request = &app.email_server.received_requests().await.unwrap()[1];
+    let confirmation_links = app.get_confirmation_links(email_request);
+    reqwest::get(confirmation_links.html)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+    let saved = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved subscription.");
+    assert_eq!(saved.status, "confirmed");
+}
+
+#[tokio::test]
+async fn resending_to_an_unknown_or_confirmed_address_sends_nothing() {
+    // Arrange
+    let app = spawn_app().await;
+    create_pending_subscriber(&app).await;
+    sqlx::query!("UPDATE subscriptions SET status = 'confirmed'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let confirmed = app
+        .post_resend_confirmation(SUBSCRIBER_EMAIL, CLIENT_IP)
+        .await;
+    let unknown = app
+        .post_resend_confirmation("someone_else@gmail.com", CLIENT_IP)
+        .await;
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    assert_eq!(confirmed.status().as_u16(), 200);
+    assert_eq!(unknown.status().as_u16(), 200);
+    // Mock verifies on Drop that we haven't sent any email
+}
+
+#[tokio::test]
+async fn resending_is_throttled_per_email_address() {
+    // Arrange
+    let app = spawn_app().await;
+    create_pending_subscriber(&app).await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(3)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - Part 1 - The first few requests go through, even from different clients
+    for client_ip in ["203.0.113.1", "203.0.113.2", "203.0.113.3"] {
+        let response = app
+            .post_resend_confirmation(SUBSCRIBER_EMAIL, client_ip)
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    // Act - Part 2 - Another one for the same address is turned away
+    let response = app
+        .post_resend_confirmation("Ursula_Le_Guin@gmail.com", "203.0.113.4")
+        .await;
+    assert_is_redirect_to(&response, "/subscriptions/resend");
+
+    // Act - Part 3 - Follow the redirect
+    let html_page = app.get_resend_confirmation_html().await;
+    assert!(html_page.contains("Too many confirmation emails were requested recently"));
+    app.dispatch_all_outbox_emails().await;
+    // Mock verifies on Drop that only three emails went out
+}
+
+#[tokio::test]
+async fn resending_is_throttled_per_client() {
+    // Arrange
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+    for i in 0..10 {
+        let response = app
+            .post_resend_confirmation(&format!("reader{}@gmail.com", i), CLIENT_IP)
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    // Act
+    let throttled = app
+        .post_resend_confirmation("reader10@gmail.com", CLIENT_IP)
+        .await;
+    let other_client = app
+        .post_resend_confirmation("reader10@gmail.com", "203.0.113.8")
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&throttled, "/subscriptions/resend");
+    assert_eq!(other_client.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn resending_to_an_invalid_address_is_rejected_with_a_400() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_resend_confirmation("definitely-not-an-email", CLIENT_IP)
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn a_client_cannot_get_around_the_limit_by_spoofing_the_forwarded_address() {
+    // Arrange
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+    for i in 0..10 {
+        let spoofed_chain = format!("198.51.100.{}, {}", i, CLIENT_IP);
+        let response = app
+            .post_resend_confirmation(&format!("reader{}@gmail.com", i), &spoofed_chain)
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    // Act
+    let response = app
+        .post_resend_confirmation(
+            "reader10@gmail.com",
+            &format!("198.51.100.10, {}", CLIENT_IP),
+        )
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/subscriptions/resend");
+}
+
+#[tokio::test]
+async fn concurrent_resend_requests_cannot_exceed_the_limit() {
+    // Arrange
+    let app = spawn_app().await;
+    create_pending_subscriber(&app).await;
+
+    let client_ips: Vec<_> = (0..10).map(|i| format!("203.0.113.{}", i)).collect();
+
+    // Act
+    let responses = futures_util::future::join_all(
+        client_ips
+            .iter()
+            .map(|client_ip| app.post_resend_confirmation(SUBSCRIBER_EMAIL, client_ip)),
+    )
+    .await;
+
+    // Assert
+    let accepted = responses
+        .iter()
+        .filter(|response| response.status().as_u16() == 200)
+        .count();
+    assert_eq!(accepted, 3);
+}
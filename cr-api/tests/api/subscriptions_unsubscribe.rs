@@ -0,0 +1,150 @@
+// tests/api/subscriptions_unsubscribe.rs
+
+use crate::helpers::{spawn_app, TestApp};
+use cr_api::domain::UnsubscribeToken;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+async fn create_confirmed_subscriber(app: &TestApp) -> Uuid {
+    let subscriber_id = Uuid::new_v4();
+    sqlx::query!(
+        "INSERT INTO subscriptions (id, email, name, subscribed_at, status)
+        VALUES ($1, 'ursula_le_guin@gmail.com', 'le guin', now(), 'confirmed')",
+        subscriber_id,
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store test subscriber.");
+    subscriber_id
+}
+
+async fn subscriber_status(app: &TestApp) -> String {
+    sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved subscription.")
+        .status
+}
+
+#[tokio::test]
+async fn unsubscribe_without_token_is_rejected_with_a_400() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::get(format!("{}/subscriptions/unsubscribe", app.address))
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn unsubscribe_with_a_forged_token_is_rejected_with_a_401() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let forged_token = format!("{}.{}", Uuid::new_v4(), "ab".repeat(32));
+
+    // Act
+    let response = app.post_unsubscribe(&forged_token).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(subscriber_status(&app).await, "confirmed");
+}
+
+#[tokio::test]
+async fn the_unsubscribe_form_does_not_unsubscribe_on_its_own() {
+    // Arrange
+    let app = spawn_app().await;
+    let subscriber_id = create_confirmed_subscriber(&app).await;
+    let token = UnsubscribeToken::generate(subscriber_id, &app.hmac_secret.0);
+
+    // Act
+    let response = app.get_unsubscribe(token.as_ref()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response.text().await.unwrap().contains(token.as_ref()));
+    assert_eq!(subscriber_status(&app).await, "confirmed");
+}
+
+#[tokio::test]
+async fn one_click_unsubscribe_marks_the_subscriber_as_unsubscribed() {
+    // Arrange
+    let app = spawn_app().await;
+    let subscriber_id = create_confirmed_subscriber(&app).await;
+    let token = UnsubscribeToken::generate(subscriber_id, &app.hmac_secret.0);
+
+    // Act
+    let response = app.post_unsubscribe(token.as_ref()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "unsubscribed");
+}
+
+#[tokio::test]
+async fn unsubscribing_sends_a_single_confirmation_email() {
+    // Arrange
+    let app = spawn_app().await;
+    let subscriber_id = create_confirmed_subscriber(&app).await;
+    let token = UnsubscribeToken::generate(subscriber_id, &app.hmac_secret.0);
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    app.post_unsubscribe(token.as_ref()).await;
+    let response = app.post_unsubscribe(token.as_ref()).await;
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let email_body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    assert_eq!(email_body["Subject"], "You've been unsubscribed");
+    assert!(email_body["TextBody"]
+        .as_str()
+        .unwrap()
+        .contains("won't receive any more issues"));
+    // Mock verifies on Drop that the second click didn't send another email
+}
+
+#[tokio::test]
+async fn an_unsubscribed_address_is_suppressed_and_cannot_subscribe_again() {
+    // Arrange
+    let app = spawn_app().await;
+    let subscriber_id = create_confirmed_subscriber(&app).await;
+    let token = UnsubscribeToken::generate(subscriber_id, &app.hmac_secret.0);
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    app.post_unsubscribe(token.as_ref()).await;
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com".into())
+        .await;
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "unsubscribed");
+    let suppression = sqlx::query!("SELECT email, source FROM suppressions")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch the suppression.");
+    assert_eq!(suppression.email, "ursula_le_guin@gmail.com");
+    assert_eq!(suppression.source, "unsubscribe");
+    // Mock verifies on Drop that only the unsubscribe confirmation went out
+}
@@ -0,0 +1,192 @@
+// tests/api/suppressions.rs
+
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+async fn create_confirmed_subscriber(app: &TestApp, email: &str) {
+    sqlx::query!(
+        "INSERT INTO subscriptions (id, email, name, subscribed_at, status)
+        VALUES ($1, $2, 'le guin', now(), 'confirmed')",
+        Uuid::new_v4(),
+        email,
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store test subscriber.");
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_manage_suppressions() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_suppressions().await;
+    let add_response = app
+        .post_add_suppression(&serde_json::json!({
+            "email": "ursula_le_guin@gmail.com",
+            "reason": "asked us to",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert_is_redirect_to(&add_response, "/login");
+}
+
+#[tokio::test]
+async fn an_added_suppression_is_listed() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_add_suppression(&serde_json::json!({
+            "email": "Ursula_Le_Guin@gmail.com",
+            "reason": "Asked us to never write again",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/suppressions");
+    let html_page = app.get_suppressions_html().await;
+    assert!(html_page.contains("The address has been added to the suppression list."));
+    assert!(html_page.contains("ursula_le_guin@gmail.com"));
+    assert!(html_page.contains("Asked us to never write again"));
+    assert!(html_page.contains("admin"));
+}
+
+#[tokio::test]
+async fn an_invalid_address_cannot_be_suppressed() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_add_suppression(&serde_json::json!({
+            "email": "definitely-not-an-email",
+            "reason": "",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/suppressions");
+    let n_suppressions = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM suppressions"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count the suppressions.");
+    assert_eq!(n_suppressions.value, 0);
+}
+
+#[tokio::test]
+async fn subscribing_with_a_suppressed_address_looks_the_same_but_sends_nothing() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_add_suppression(&serde_json::json!({
+        "email": "ursula_le_guin@gmail.com",
+        "reason": "Hard bounced",
+    }))
+    .await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_subscriptions("name=le%20guin&email=URSULA_LE_GUIN%40gmail.com".into())
+        .await;
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let n_subscribers = sqlx::query!(r#"SELECT COUNT(*) as "value!" FROM subscriptions"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count the subscribers.");
+    assert_eq!(n_subscribers.value, 0);
+    // Mock verifies on Drop that we haven't sent the confirmation email
+}
+
+#[tokio::test]
+async fn suppressed_subscribers_are_not_sent_newsletters() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app, "ursula_le_guin@gmail.com").await;
+    create_confirmed_subscriber(&app, "octavia_butler@gmail.com").await;
+    app.test_user.login(&app).await;
+    app.post_add_suppression(&serde_json::json!({
+        "email": "ursula_le_guin@gmail.com",
+        "reason": "Asked us to",
+    }))
+    .await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletter");
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    assert_eq!(body["To"], "octavia_butler@gmail.com");
+    // Mock verifies on Drop that only the subscriber who isn't suppressed got the newsletter
+}
+
+#[tokio::test]
+async fn a_removed_suppression_lets_the_address_subscribe_again() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_add_suppression(&serde_json::json!({
+        "email": "ursula_le_guin@gmail.com",
+        "reason": "Asked us to",
+    }))
+    .await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_remove_suppression("ursula_le_guin@gmail.com")
+        .await;
+    assert_is_redirect_to(&response, "/admin/suppressions");
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com".into())
+        .await;
+    app.dispatch_all_outbox_emails().await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(!app
+        .get_suppressions_html()
+        .await
+        .contains("<td>ursula_le_guin@gmail.com</td>"));
+    // Mock verifies on Drop that the confirmation email went out
+}